@@ -0,0 +1,196 @@
+use serde::Serialize;
+use serde_json::{json, Value};
+
+use crate::control::parse_time;
+use crate::innertube::{self, text};
+use crate::now_playing::Track;
+
+/// Songs-only filter for the search endpoint, same as `searchMusic` in `src/api/yt.ts`
+const SONGS_FILTER: &str = "EgWKAQIIAWoMEAMQBBAJEA4QChAF";
+
+fn best_thumb(renderer: &Value) -> String {
+    renderer["thumbnail"]["musicThumbnailRenderer"]["thumbnail"]["thumbnails"]
+        .as_array()
+        .and_then(|t| t.last())
+        .and_then(|t| t["url"].as_str())
+        .unwrap_or_default()
+        .to_string()
+}
+
+fn flex_column(renderer: &Value, i: usize) -> String {
+    text(&renderer["flexColumns"][i]["musicResponsiveListItemFlexColumnRenderer"]["text"])
+}
+
+/// Parse a search result row. Returns `None` for rows that can't be played.
+fn parse_search_row(item: &Value) -> Option<Track> {
+    let r = &item["musicResponsiveListItemRenderer"];
+    let video_id = r["overlay"]["musicItemThumbnailOverlayRenderer"]["content"]["musicPlayButtonRenderer"]
+        ["playNavigationEndpoint"]["watchEndpoint"]["videoId"]
+        .as_str()?
+        .to_string();
+    let title = flex_column(r, 0);
+    if title.is_empty() {
+        return None;
+    }
+
+    // "Artist • Album • 3:45"
+    let subtitle = flex_column(r, 1);
+    let parts: Vec<&str> = subtitle.split(" \u{2022} ").collect();
+    let album = if parts.len() > 2 { parts[2] } else { "" };
+    let duration = if parts.len() > 1 { parse_time(parts[parts.len() - 1]).unwrap_or(0.0) } else { 0.0 };
+
+    Some(Track {
+        video_id,
+        title,
+        artist: parts.first().copied().unwrap_or_default().to_string(),
+        album: album.to_string(),
+        thumb_url: best_thumb(r),
+        duration,
+    })
+}
+
+/// Search YouTube Music for songs
+pub async fn search_songs(app: &tauri::AppHandle, query: &str) -> Result<Vec<Track>, String> {
+    let res = innertube::call(app, "search", json!({ "query": query, "params": SONGS_FILTER })).await?;
+    let sections = res["contents"]["tabbedSearchResultsRenderer"]["tabs"][0]["tabRenderer"]["content"]
+        ["sectionListRenderer"]["contents"]
+        .as_array()
+        .cloned()
+        .unwrap_or_default();
+
+    Ok(sections
+        .iter()
+        .filter_map(|s| s["musicShelfRenderer"]["contents"].as_array())
+        .flatten()
+        .filter_map(parse_search_row)
+        .collect())
+}
+
+/// A track as it sits in a playlist
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PlaylistItem {
+    #[serde(flatten)]
+    pub track: Track,
+    /// Identifies this occurrence of the track, needed to remove or move it
+    pub set_video_id: String,
+}
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Playlist {
+    pub id: String,
+    pub title: String,
+    pub items: Vec<PlaylistItem>,
+}
+
+/// Parse a playlist row, same as `parseTracks` in `src/api/yt.ts`
+fn parse_playlist_row(item: &Value) -> Option<PlaylistItem> {
+    let r = &item["musicResponsiveListItemRenderer"];
+    let title = flex_column(r, 0);
+    if title.is_empty() || title == "Song deleted" {
+        return None;
+    }
+    let video_id = r["overlay"]["musicItemThumbnailOverlayRenderer"]["content"]["musicPlayButtonRenderer"]
+        ["playNavigationEndpoint"]["watchEndpoint"]["videoId"]
+        .as_str()
+        .or_else(|| r["playlistItemData"]["videoId"].as_str())?
+        .to_string();
+
+    // "Artist • Album"
+    let subtitle = flex_column(r, 1);
+    let parts: Vec<&str> = subtitle.split(" \u{2022} ").collect();
+    let album = if parts.len() > 1 { parts[parts.len() - 1] } else { "" };
+    let duration = text(&r["fixedColumns"][0]["musicResponsiveListItemFixedColumnRenderer"]["text"]);
+
+    Some(PlaylistItem {
+        track: Track {
+            video_id,
+            title,
+            artist: parts.first().copied().unwrap_or_default().to_string(),
+            album: album.to_string(),
+            thumb_url: best_thumb(r),
+            duration: parse_time(&duration).unwrap_or(0.0),
+        },
+        set_video_id: r["playlistItemData"]["playlistSetVideoId"].as_str().unwrap_or_default().to_string(),
+    })
+}
+
+fn continuation_token(items: &[Value]) -> Option<String> {
+    let r = &items.iter().find(|i| !i["continuationItemRenderer"].is_null())?["continuationItemRenderer"];
+    r["continuationEndpoint"]["continuationCommand"]["token"]
+        .as_str()
+        .or_else(|| r["nextContinuationData"]["continuation"].as_str())
+        .map(str::to_string)
+}
+
+/// Rows and the next page's token from a continuation response
+fn continuation_page(res: &Value) -> (Vec<Value>, Option<String>) {
+    let cc = &res["continuationContents"];
+    if !cc.is_null() {
+        let shelf = ["musicPlaylistShelfContinuation", "musicShelfContinuation", "sectionListContinuation"]
+            .iter()
+            .map(|k| &cc[*k])
+            .find(|s| !s.is_null());
+        return match shelf {
+            Some(shelf) => (
+                shelf["contents"].as_array().cloned().unwrap_or_default(),
+                shelf["continuations"][0]["nextContinuationData"]["continuation"].as_str().map(str::to_string),
+            ),
+            None => (Vec::new(), None),
+        };
+    }
+
+    let actions = res["onResponseReceivedActions"].as_array().or_else(|| res["onResponseReceivedEndpoints"].as_array());
+    let items = actions
+        .into_iter()
+        .flatten()
+        .filter_map(|a| a["appendContinuationItemsAction"]["continuationItems"].as_array())
+        .last()
+        .cloned()
+        .unwrap_or_default();
+    let next = continuation_token(&items);
+    (items, next)
+}
+
+/// Fetch a playlist with all its tracks, following continuations
+pub async fn playlist(app: &tauri::AppHandle, playlist_id: &str) -> Result<Playlist, String> {
+    let id = playlist_id.strip_prefix("VL").unwrap_or(playlist_id);
+    let res = innertube::call(app, "browse", json!({ "browseId": format!("VL{}", id) })).await?;
+
+    let single = &res["contents"]["singleColumnBrowseResultsRenderer"]["tabs"][0]["tabRenderer"]["content"]
+        ["sectionListRenderer"]["contents"];
+    let two = &res["contents"]["twoColumnBrowseResultsRenderer"];
+    let shelf = if single[0]["musicPlaylistShelfRenderer"].is_null() {
+        &two["secondaryContents"]["sectionListRenderer"]["contents"][0]["musicPlaylistShelfRenderer"]
+    } else {
+        &single[0]["musicPlaylistShelfRenderer"]
+    };
+    if shelf.is_null() {
+        return Err(format!("Playlist {} not found", id));
+    }
+
+    let header = &two["tabs"][0]["tabRenderer"]["content"]["sectionListRenderer"]["contents"][0]
+        ["musicResponsiveHeaderRenderer"]["title"];
+    let title = [header, &res["header"]["musicDetailHeaderRenderer"]["title"]]
+        .into_iter()
+        .map(text)
+        .find(|t| !t.is_empty())
+        .unwrap_or_else(|| id.to_string());
+
+    let contents = shelf["contents"].as_array().cloned().unwrap_or_default();
+    let mut items: Vec<PlaylistItem> = contents.iter().filter_map(parse_playlist_row).collect();
+    let mut continuation = shelf["continuations"][0]["nextContinuationData"]["continuation"]
+        .as_str()
+        .map(str::to_string)
+        .or_else(|| continuation_token(&contents));
+
+    while let Some(token) = continuation {
+        let res = innertube::call(app, "browse", json!({ "continuation": &token })).await?;
+        let (rows, next) = continuation_page(&res);
+        items.extend(rows.iter().filter_map(parse_playlist_row));
+        continuation = next.filter(|n| *n != token);
+    }
+
+    Ok(Playlist { id: id.to_string(), title, items })
+}
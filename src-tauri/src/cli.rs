@@ -0,0 +1,129 @@
+use serde_json::Value;
+use std::io::{BufRead, BufReader, Write};
+use std::net::TcpStream;
+use std::time::Duration;
+
+use crate::control::{self, parse_time, Envelope, Request, Response, CONTROL_PORT};
+
+const USAGE: &str = "Usage: goymusic <command>
+
+Commands:
+  play-pause           Toggle playback
+  next                 Skip to the next track
+  prev                 Go back to the previous track
+  seek <time>          Jump to a position, e.g. 1:23 or 83
+  volume <0-100>       Set the volume
+  now-playing [--json] Show the current track
+  enqueue <url>        Add a YouTube Music track or playlist to the queue";
+
+const COMMANDS: [&str; 7] = ["play-pause", "next", "prev", "seek", "volume", "now-playing", "enqueue"];
+
+fn send(request: Request) -> Result<Response, String> {
+    let not_running = || "GoyMusic is not running".to_string();
+    // Written by the running instance on startup
+    let token = control::token_path().and_then(|p| std::fs::read_to_string(p).ok()).ok_or_else(not_running)?;
+    let addr = ("127.0.0.1", CONTROL_PORT);
+    let stream = TcpStream::connect(addr).map_err(|_| not_running())?;
+    // Enqueueing a track waits on a YouTube Music lookup
+    stream.set_read_timeout(Some(Duration::from_secs(30))).map_err(|e| e.to_string())?;
+
+    let envelope = Envelope { token: token.trim().to_string(), request };
+    let mut line = serde_json::to_string(&envelope).map_err(|e| e.to_string())?;
+    line.push('\n');
+    (&stream).write_all(line.as_bytes()).map_err(|e| e.to_string())?;
+
+    let mut reply = String::new();
+    BufReader::new(&stream).read_line(&mut reply).map_err(|e| e.to_string())?;
+    serde_json::from_str(&reply).map_err(|e| format!("Invalid reply from GoyMusic: {}", e))
+}
+
+fn format_time(secs: f64) -> String {
+    let s = secs.max(0.0) as u64;
+    format!("{}:{:02}", s / 60, s % 60)
+}
+
+fn print_now_playing(np: &Value) {
+    let track = &np["track"];
+    if track.is_null() {
+        println!("Nothing is playing");
+        return;
+    }
+    let state = if np["is_playing"].as_bool().unwrap_or(false) { "playing" } else { "paused" };
+    println!(
+        "{} — {} [{}/{}] ({})",
+        track["title"].as_str().unwrap_or_default(),
+        track["artist"].as_str().unwrap_or_default(),
+        format_time(np["current_time"].as_f64().unwrap_or(0.0)),
+        format_time(track["duration"].as_f64().unwrap_or(0.0)),
+        state,
+    );
+}
+
+fn execute(command: &str, rest: &[String]) -> Result<(), String> {
+    let json = rest.iter().any(|a| a == "--json");
+    let args: Vec<String> = rest.iter().filter(|a| !a.starts_with("--")).cloned().collect();
+
+    // Catch obvious mistakes before bothering the running instance
+    match command {
+        "seek" if args.first().and_then(|a| parse_time(a)).is_none() => {
+            return Err("seek needs a time like 1:23 or 83".to_string());
+        }
+        "volume" | "enqueue" if args.is_empty() => return Err(format!("{} needs an argument", command)),
+        _ => {}
+    }
+
+    let response = send(Request { command: command.to_string(), args })?;
+    if !response.ok {
+        return Err(response.error.unwrap_or_else(|| "Command failed".to_string()));
+    }
+
+    match command {
+        "now-playing" if json => println!("{}", response.data),
+        "now-playing" => print_now_playing(&response.data),
+        "enqueue" => {
+            let data = &response.data;
+            match data["kind"].as_str() {
+                Some("track") => println!("Queued {}", data["track"]["title"].as_str().unwrap_or_default()),
+                _ => println!("Queued playlist {}", data["playlistId"].as_str().unwrap_or_default()),
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+/// Release builds use the GUI subsystem and start without a console, so output would go
+/// nowhere. Borrow the console of the terminal we were started from, if any.
+#[cfg(windows)]
+fn attach_console() {
+    use windows_sys::Win32::System::Console::{AttachConsole, ATTACH_PARENT_PROCESS};
+    // Fails harmlessly when there's no parent console or we already have one
+    unsafe {
+        AttachConsole(ATTACH_PARENT_PROCESS);
+    }
+}
+
+/// Handle a CLI invocation. Returns the exit code, or `None` when the arguments
+/// aren't a subcommand and the app should start normally.
+pub fn main(args: &[String]) -> Option<i32> {
+    let command = args.first()?.as_str();
+    let help = matches!(command, "help" | "--help" | "-h");
+    if !help && !COMMANDS.contains(&command) {
+        return None;
+    }
+    #[cfg(windows)]
+    attach_console();
+
+    if help {
+        println!("{}", USAGE);
+        return Some(0);
+    }
+
+    match execute(command, &args[1..]) {
+        Ok(()) => Some(0),
+        Err(e) => {
+            eprintln!("goymusic {}: {}", command, e);
+            Some(1)
+        }
+    }
+}
@@ -0,0 +1,225 @@
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::io::{BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream};
+use std::path::{Path, PathBuf};
+use tauri::{Emitter, Manager};
+
+use crate::catalog;
+use crate::links;
+use crate::now_playing::{NowPlayingState, QueueState};
+
+/// Loopback port the running instance listens on for CLI commands
+pub const CONTROL_PORT: u16 = 38591;
+
+/// Bundle identifier from tauri.conf.json, which names the app data dir. The CLI runs before
+/// Tauri starts, so it can't ask the config; a test keeps the two in sync.
+const APP_IDENTIFIER: &str = "com.silas.tauri-app";
+
+const TOKEN_FILE: &str = "control-token";
+
+/// One command per connection, sent as a single JSON line
+#[derive(Serialize, Deserialize)]
+pub struct Request {
+    pub command: String,
+    #[serde(default)]
+    pub args: Vec<String>,
+}
+
+/// A [`Request`] on the control port, with the secret from [`token_path`]
+#[derive(Serialize, Deserialize)]
+pub struct Envelope {
+    pub token: String,
+    #[serde(flatten)]
+    pub request: Request,
+}
+
+/// Reply to a [`Request`], also a single JSON line
+#[derive(Serialize, Deserialize)]
+pub struct Response {
+    pub ok: bool,
+    #[serde(default)]
+    pub data: Value,
+    #[serde(default)]
+    pub error: Option<String>,
+}
+
+/// Parse `83`, `1:23` or `1:02:03` into seconds. Minutes and seconds after the first part must be under 60.
+pub fn parse_time(s: &str) -> Option<f64> {
+    let parts: Vec<&str> = s.split(':').collect();
+    if parts.len() > 3 {
+        return None;
+    }
+    parts.iter().enumerate().try_fold(0.0, |acc, (i, part)| {
+        let v: f64 = part.trim().parse().ok()?;
+        let valid = v.is_finite() && v >= 0.0 && (i == 0 || v < 60.0);
+        valid.then_some(acc * 60.0 + v)
+    })
+}
+
+/// File holding the secret that control clients must send, readable only by the current user.
+/// For the CLI; the app itself writes it to [`app_token_path`], which is the same file.
+pub fn token_path() -> Option<PathBuf> {
+    dirs::data_dir().map(|dir| dir.join(APP_IDENTIFIER).join(TOKEN_FILE))
+}
+
+fn app_token_path(app: &tauri::AppHandle) -> Result<PathBuf, String> {
+    app.path().app_data_dir().map(|dir| dir.join(TOKEN_FILE)).map_err(|e| e.to_string())
+}
+
+fn write_token(path: &Path, token: &str) -> std::io::Result<()> {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+    options.open(path)?.write_all(token.as_bytes())
+}
+
+fn arg<'a>(req: &'a Request, name: &str) -> Result<&'a str, String> {
+    req.args.first().map(String::as_str).ok_or_else(|| format!("Missing {}", name))
+}
+
+fn require_track(app: &tauri::AppHandle) -> Result<(), String> {
+    let playing = app.state::<NowPlayingState>().0.lock().unwrap().track.is_some();
+    if playing { Ok(()) } else { Err("Nothing is playing".to_string()) }
+}
+
+/// Run a command against the player. Playback changes go through the same events as media keys.
+async fn dispatch(app: &tauri::AppHandle, req: &Request) -> Result<Value, String> {
+    match req.command.as_str() {
+        "play-pause" => app.emit("media-play-pause", ()).map_err(|e| e.to_string())?,
+        "play" | "pause" => {
+            require_track(app)?;
+            let playing = app.state::<NowPlayingState>().0.lock().unwrap().is_playing;
+            if playing != (req.command == "play") {
+                app.emit("media-play-pause", ()).map_err(|e| e.to_string())?;
+            }
+        }
+        "next" => app.emit("media-next", ()).map_err(|e| e.to_string())?,
+        "prev" => app.emit("media-prev", ()).map_err(|e| e.to_string())?,
+        "seek" => {
+            require_track(app)?;
+            let raw = arg(req, "time")?;
+            let time = parse_time(raw).ok_or_else(|| format!("Invalid time \"{}\"", raw))?;
+            app.emit("media-seek", time).map_err(|e| e.to_string())?;
+        }
+        "volume" => {
+            let raw = arg(req, "volume")?;
+            let volume: f64 = raw
+                .parse()
+                .ok()
+                .filter(|v| (0.0..=100.0).contains(v))
+                .ok_or_else(|| format!("Volume must be 0-100, got \"{}\"", raw))?;
+            app.emit("media-volume", volume).map_err(|e| e.to_string())?;
+        }
+        "now-playing" => {
+            let np = app.state::<NowPlayingState>().0.lock().unwrap().clone();
+            return serde_json::to_value(np).map_err(|e| e.to_string());
+        }
+        "queue" => {
+            let queue = app.state::<QueueState>().0.lock().unwrap().clone();
+            return serde_json::to_value(queue).map_err(|e| e.to_string());
+        }
+        "search" => {
+            let query = req.args.join(" ");
+            if query.trim().is_empty() {
+                return Err("Missing search query".to_string());
+            }
+            let tracks = catalog::search_songs(app, &query).await?;
+            return serde_json::to_value(tracks).map_err(|e| e.to_string());
+        }
+        "enqueue" => {
+            let url = arg(req, "URL")?;
+            let link = links::parse(url).ok_or_else(|| format!("Not a YouTube Music link: {}", url))?;
+            let open = links::resolve(app, link).await?;
+            app.emit("enqueue-link", &open).map_err(|e| e.to_string())?;
+            return serde_json::to_value(open).map_err(|e| e.to_string());
+        }
+        other => return Err(format!("Unknown command \"{}\"", other)),
+    }
+    Ok(Value::Null)
+}
+
+/// Run a command and wrap the outcome for the wire. Shared with the remote control server.
+pub async fn execute(app: &tauri::AppHandle, req: &Request) -> Response {
+    match dispatch(app, req).await {
+        Ok(data) => Response { ok: true, data, error: None },
+        Err(e) => Response { ok: false, data: Value::Null, error: Some(e) },
+    }
+}
+
+fn handle(app: &tauri::AppHandle, token: &str, stream: TcpStream) -> std::io::Result<()> {
+    let mut line = String::new();
+    BufReader::new(&stream).read_line(&mut line)?;
+
+    let fail = |error: String| Response { ok: false, data: Value::Null, error: Some(error) };
+    let response = match serde_json::from_str::<Envelope>(&line) {
+        Ok(env) if env.token != token => fail("Invalid control token".to_string()),
+        Ok(env) => tauri::async_runtime::block_on(execute(app, &env.request)),
+        Err(e) => fail(format!("Bad request: {}", e)),
+    };
+
+    let mut out = serde_json::to_string(&response).unwrap_or_else(|_| json!({ "ok": false }).to_string());
+    out.push('\n');
+    (&stream).write_all(out.as_bytes())
+}
+
+/// Listen for CLI commands on the loopback control port. Any local process can connect, so
+/// requests must carry a token that is regenerated on every launch.
+pub fn start_server(app: tauri::AppHandle) {
+    let token = uuid::Uuid::new_v4().simple().to_string();
+    let written = app_token_path(&app).and_then(|path| write_token(&path, &token).map_err(|e| e.to_string()));
+    if let Err(e) = written {
+        eprintln!("Control server disabled, can't write token: {}", e);
+        return;
+    }
+
+    let listener = match TcpListener::bind(("127.0.0.1", CONTROL_PORT)) {
+        Ok(l) => l,
+        Err(e) => {
+            eprintln!("Control server disabled, port {} unavailable: {}", CONTROL_PORT, e);
+            return;
+        }
+    };
+
+    std::thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            let (app, token) = (app.clone(), token.clone());
+            std::thread::spawn(move || {
+                if let Err(e) = handle(&app, &token, stream) {
+                    eprintln!("Control connection failed: {}", e);
+                }
+            });
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn app_identifier_matches_tauri_config() {
+        let config: Value = serde_json::from_str(include_str!("../tauri.conf.json")).unwrap();
+        assert_eq!(config["identifier"], APP_IDENTIFIER);
+    }
+
+    #[test]
+    fn parse_time_accepts_clock_formats() {
+        assert_eq!(parse_time("83"), Some(83.0));
+        assert_eq!(parse_time("1:23"), Some(83.0));
+        assert_eq!(parse_time("1:02:03"), Some(3723.0));
+        assert_eq!(parse_time("90:00"), Some(5400.0));
+        assert_eq!(parse_time("0:01.5"), Some(1.5));
+    }
+
+    #[test]
+    fn parse_time_rejects_out_of_range_parts() {
+        for bad in ["", "-5", "1:75", "1:60", "1:02:60", "inf", "NaN", "1:inf", "1:2:3:4", "a:10"] {
+            assert_eq!(parse_time(bad), None, "{:?}", bad);
+        }
+    }
+}
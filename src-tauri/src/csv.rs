@@ -0,0 +1,80 @@
+/// Quote a field if it contains a delimiter, quote or line break (RFC 4180)
+pub fn escape(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+/// One CSV line, CRLF-terminated
+pub fn row<S: AsRef<str>>(fields: &[S]) -> String {
+    let mut line = fields.iter().map(|f| escape(f.as_ref())).collect::<Vec<_>>().join(",");
+    line.push_str("\r\n");
+    line
+}
+
+/// Parse CSV text into rows of fields. Handles quoted fields with embedded delimiters,
+/// doubled quotes and line breaks, CRLF or LF line endings and a leading BOM.
+pub fn parse(text: &str) -> Vec<Vec<String>> {
+    let text = text.strip_prefix('\u{feff}').unwrap_or(text);
+    let mut rows = Vec::new();
+    let mut row = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = text.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if quoted {
+            match c {
+                '"' if chars.peek() == Some(&'"') => {
+                    field.push('"');
+                    chars.next();
+                }
+                '"' => quoted = false,
+                c => field.push(c),
+            }
+            continue;
+        }
+        match c {
+            '"' if field.is_empty() => quoted = true,
+            ',' => row.push(std::mem::take(&mut field)),
+            '\r' if chars.peek() == Some(&'\n') => {}
+            '\n' | '\r' => {
+                row.push(std::mem::take(&mut field));
+                rows.push(std::mem::take(&mut row));
+            }
+            c => field.push(c),
+        }
+    }
+    if !field.is_empty() || !row.is_empty() {
+        row.push(field);
+        rows.push(row);
+    }
+    // Drop blank lines
+    rows.retain(|r| !(r.len() == 1 && r[0].is_empty()));
+    rows
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_quoted_fields() {
+        let rows = parse("a,\"b, c\",\"say \"\"hi\"\"\"\r\n\"multi\nline\",,x\n");
+        assert_eq!(rows, [vec!["a", "b, c", "say \"hi\""], vec!["multi\nline", "", "x"]]);
+    }
+
+    #[test]
+    fn skips_bom_and_blank_lines() {
+        let rows = parse("\u{feff}title,artist\r\n\r\nSong,Band");
+        assert_eq!(rows, [vec!["title", "artist"], vec!["Song", "Band"]]);
+    }
+
+    #[test]
+    fn row_round_trips_through_parse() {
+        let fields = ["plain", "with, comma", "with \"quotes\"", "two\r\nlines", ""];
+        assert_eq!(parse(&row(&fields)), [fields.to_vec()]);
+    }
+}
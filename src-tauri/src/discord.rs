@@ -0,0 +1,261 @@
+use discord_rich_presence::{activity, DiscordIpc, DiscordIpcClient};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tauri::{Manager, State};
+
+use crate::now_playing::NowPlayingState;
+use crate::settings::{DiscordSettings, SettingsState};
+
+pub struct DiscordState(pub Mutex<DiscordSession>);
+
+#[derive(Default)]
+pub struct DiscordSession {
+    /// Connected IPC client along with the application ID it was opened for
+    client: Option<(String, DiscordIpcClient)>,
+    /// When playback last stopped
+    idle_since: Option<Instant>,
+    /// Whether the activity was already cleared for this idle period
+    idle_cleared: bool,
+}
+
+impl DiscordSession {
+    fn idle_expired(&self, timeout_secs: u64) -> bool {
+        timeout_secs > 0 && self.idle_since.is_some_and(|t| t.elapsed().as_secs() >= timeout_secs)
+    }
+
+    fn clear(&mut self) {
+        if let Some((_, client)) = self.client.as_mut() {
+            let _ = client.clear_activity();
+        }
+    }
+}
+
+/// Fill `{title}`, `{artist}` and `{album}` placeholders in one pass, so braces inside the
+/// metadata itself are left alone
+fn render_template(template: &str, title: &str, artist: &str, album: &str) -> String {
+    let mut out = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(open) = rest.find('{') {
+        out.push_str(&rest[..open]);
+        rest = &rest[open..];
+        let value = [("{title}", title), ("{artist}", artist), ("{album}", album)]
+            .into_iter()
+            .find(|(placeholder, _)| rest.starts_with(placeholder));
+        match value {
+            Some((placeholder, value)) => {
+                out.push_str(value);
+                rest = &rest[placeholder.len()..];
+            }
+            None => {
+                out.push('{');
+                rest = &rest[1..];
+            }
+        }
+    }
+    out.push_str(rest);
+    out.trim().to_string()
+}
+
+/// Discord rejects text fields shorter than 2 or longer than 128 characters
+fn fit_field(s: String) -> Option<String> {
+    match s.chars().count() {
+        0..=1 => None,
+        2..=128 => Some(s),
+        _ => Some(s.chars().take(127).collect::<String>() + "…"),
+    }
+}
+
+fn disconnect(conn: &mut Option<(String, DiscordIpcClient)>) {
+    if let Some((_, mut client)) = conn.take() {
+        let _ = client.close();
+    }
+}
+
+/// Clear the presence and close the IPC connection before exiting
+pub fn shutdown(app: &tauri::AppHandle) {
+    let Some(state) = app.try_state::<DiscordState>() else { return };
+    let mut session = state.0.lock().unwrap();
+    session.clear();
+    disconnect(&mut session.client);
+}
+
+/// Drop the presence right away when Discord is switched off or the client ID changes,
+/// instead of waiting for the next track update
+pub fn reconfigure(app: &tauri::AppHandle, cfg: &DiscordSettings) {
+    let state = app.state::<DiscordState>();
+    let mut session = state.0.lock().unwrap();
+    let stale = session.client.as_ref().is_some_and(|(id, _)| *id != cfg.client_id);
+    if !cfg.enabled || cfg.incognito || stale {
+        session.clear();
+        disconnect(&mut session.client);
+    }
+}
+
+/// Clear the presence once playback has been idle longer than the configured timeout
+pub fn spawn_idle_watcher(app: tauri::AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        loop {
+            tokio::time::sleep(Duration::from_secs(15)).await;
+            let timeout = app.state::<SettingsState>().0.lock().unwrap().discord.idle_timeout_secs;
+            let state = app.state::<DiscordState>();
+            let mut session = state.0.lock().unwrap();
+            if !session.idle_cleared && session.idle_expired(timeout) {
+                session.clear();
+                session.idle_cleared = true;
+            }
+        }
+    });
+}
+
+#[tauri::command]
+pub fn ytm_update_discord_rpc(
+    state: State<'_, DiscordState>,
+    settings: State<'_, SettingsState>,
+    now_playing: State<'_, NowPlayingState>,
+    title: String,
+    artist: String,
+    album: Option<String>,
+    video_id: String,
+    thumb_url: String,
+    playlist_id: Option<String>,
+    is_playing: bool,
+    current_time: f64,
+    duration: f64,
+) -> Result<(), String> {
+    let cfg = settings.0.lock().unwrap().discord.clone();
+    let mut session = state.0.lock().unwrap();
+
+    if is_playing {
+        session.idle_since = None;
+        session.idle_cleared = false;
+    } else if session.idle_since.is_none() {
+        session.idle_since = Some(Instant::now());
+    }
+    if session.idle_expired(cfg.idle_timeout_secs) {
+        session.clear();
+        session.idle_cleared = true;
+        return Ok(());
+    }
+
+    let conn = &mut session.client;
+
+    if !cfg.enabled {
+        disconnect(conn);
+        return Ok(());
+    }
+
+    // Reconnect if the application ID was changed in settings
+    if conn.as_ref().is_some_and(|(id, _)| *id != cfg.client_id) {
+        disconnect(conn);
+    }
+
+    if conn.is_none() {
+        if let Ok(mut client) = DiscordIpcClient::new(&cfg.client_id) {
+            if client.connect().is_ok() {
+                *conn = Some((cfg.client_id.clone(), client));
+            }
+        }
+    }
+
+    let Some((_, client)) = conn.as_mut() else {
+        return Ok(());
+    };
+
+    let hidden_playlist = playlist_id
+        .as_ref()
+        .is_some_and(|id| cfg.hidden_playlists.contains(id));
+    if cfg.incognito || hidden_playlist || (cfg.hide_when_paused && !is_playing) {
+        let _ = client.clear_activity();
+        return Ok(());
+    }
+
+    let album = album.unwrap_or_default();
+    let large_img = if thumb_url.is_empty() { "icon".to_string() } else { thumb_url };
+    let large_text = if album.is_empty() { "GoyMusic".to_string() } else { album.clone() };
+    let large_text = fit_field(large_text).unwrap_or_else(|| "GoyMusic".to_string());
+
+    let (small_img, small_text) = if is_playing {
+        (cfg.playing_image.as_str(), "Playing")
+    } else {
+        (cfg.paused_image.as_str(), "Paused")
+    };
+    let small_text = if crate::rating::is_liked(&now_playing, &video_id) {
+        format!("{} · Liked", small_text)
+    } else {
+        small_text.to_string()
+    };
+
+    let mut assets = activity::Assets::new()
+        .large_image(large_img.as_str())
+        .large_text(large_text.as_str());
+    if !small_img.is_empty() {
+        assets = assets.small_image(small_img).small_text(small_text.as_str());
+    }
+
+    let mut act = activity::Activity::new()
+        .activity_type(activity::ActivityType::Listening)
+        .assets(assets);
+
+    let state_text = fit_field(render_template(&cfg.state_template, &title, &artist, &album));
+    if let Some(s) = state_text.as_deref() {
+        act = act.state(s);
+    }
+
+    let listen_url = if video_id.is_empty() {
+        "https://music.youtube.com".to_string()
+    } else {
+        format!("https://music.youtube.com/watch?v={}", video_id)
+    };
+
+    let mut buttons = Vec::new();
+    if cfg.show_listen_button {
+        buttons.push(activity::Button::new("Listen", &listen_url));
+    }
+    if cfg.show_download_button {
+        buttons.push(activity::Button::new("Download", "https://github.com/lonestill/goymusic"));
+    }
+    if !buttons.is_empty() {
+        act = act.buttons(buttons);
+    }
+
+    let details = if title.is_empty() {
+        Some("Browsing...".to_string())
+    } else if is_playing && duration > 0.0 {
+        fit_field(render_template(&cfg.details_template, &title, &artist, &album))
+    } else {
+        fit_field(render_template(&cfg.paused_template, &title, &artist, &album))
+    };
+    if let Some(d) = details.as_deref() {
+        act = act.details(d);
+    }
+
+    if is_playing && duration > 0.0 {
+        let now = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).unwrap().as_secs() as i64;
+        let start = now - (current_time as i64);
+        let end = start + (duration as i64);
+        act = act.timestamps(activity::Timestamps::new().start(start).end(end));
+    }
+
+    let _ = client.set_activity(act);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn render_template_fills_placeholders() {
+        assert_eq!(render_template("{title} by {artist}", "Song", "Band", "Album"), "Song by Band");
+        assert_eq!(render_template(" {album} {{title}} {year} ", "Song", "Band", "Album"), "Album {Song} {year}");
+        assert_eq!(render_template("{title}", "", "Band", "Album"), "");
+    }
+
+    #[test]
+    fn render_template_leaves_placeholders_in_metadata_alone() {
+        assert_eq!(
+            render_template("{title} - {artist} ({album})", "What is {artist}?", "Band", "{title}"),
+            "What is {artist}? - Band ({title})"
+        );
+    }
+}
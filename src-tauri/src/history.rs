@@ -0,0 +1,303 @@
+use rusqlite::{params, Connection};
+use serde::Serialize;
+use std::sync::Mutex;
+use tauri::{Manager, State};
+
+use crate::plays::Play;
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS plays (
+    id INTEGER PRIMARY KEY,
+    video_id TEXT NOT NULL,
+    title TEXT NOT NULL,
+    artist TEXT NOT NULL,
+    album TEXT NOT NULL,
+    duration REAL NOT NULL,
+    started_at INTEGER NOT NULL,
+    listened REAL NOT NULL,
+    completed INTEGER NOT NULL,
+    skipped INTEGER NOT NULL
+);
+CREATE INDEX IF NOT EXISTS plays_started_at ON plays (started_at);
+";
+
+/// Page size cap for `history_query`
+const MAX_PAGE: u32 = 500;
+
+pub struct HistoryState(pub Mutex<Connection>);
+
+/// One recorded play
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HistoryEntry {
+    pub id: i64,
+    pub video_id: String,
+    pub title: String,
+    pub artist: String,
+    pub album: String,
+    /// Track length in seconds, 0 if unknown
+    pub duration: f64,
+    /// Unix seconds
+    pub started_at: i64,
+    /// Seconds actually played
+    pub listened: f64,
+    pub completed: bool,
+    pub skipped: bool,
+}
+
+#[derive(Serialize)]
+pub struct HistoryPage {
+    pub entries: Vec<HistoryEntry>,
+    /// Entries in the whole range, for pagination
+    pub total: u64,
+}
+
+fn init(conn: &Connection) -> rusqlite::Result<()> {
+    conn.pragma_update(None, "journal_mode", "WAL")?;
+    conn.execute_batch(SCHEMA)
+}
+
+/// Open `history.sqlite3` in the app data dir, falling back to an in-memory database so
+/// the rest of the app keeps working if the file can't be used
+pub fn open(app: &tauri::AppHandle) -> HistoryState {
+    let on_disk = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| e.to_string())
+        .and_then(|dir| {
+            std::fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+            let conn = Connection::open(dir.join("history.sqlite3")).map_err(|e| e.to_string())?;
+            init(&conn).map_err(|e| e.to_string())?;
+            Ok(conn)
+        });
+
+    let conn = on_disk.unwrap_or_else(|e| {
+        eprintln!("Failed to open history database, history won't be kept: {}", e);
+        let conn = Connection::open_in_memory().expect("in-memory database");
+        init(&conn).expect("history schema");
+        conn
+    });
+    HistoryState(Mutex::new(conn))
+}
+
+/// Store a finished play. Plays shorter than a second are noise from skipping through the queue.
+pub fn record(app: &tauri::AppHandle, play: &Play, skipped: bool) {
+    let Some(state) = app.try_state::<HistoryState>() else { return };
+    if let Err(e) = insert(&state.0.lock().unwrap(), play, skipped) {
+        eprintln!("Failed to record play: {}", e);
+    }
+}
+
+/// Returns how many rows were written: 0 for plays under a second
+fn insert(conn: &Connection, play: &Play, skipped: bool) -> rusqlite::Result<usize> {
+    if play.listened < 1.0 {
+        return Ok(0);
+    }
+    let track = &play.track;
+    conn.execute(
+        "INSERT INTO plays (video_id, title, artist, album, duration, started_at, listened, completed, skipped)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+        params![
+            track.video_id,
+            track.title,
+            track.artist,
+            track.album,
+            track.duration,
+            play.started_at,
+            play.listened,
+            play.completed(),
+            skipped,
+        ],
+    )
+}
+
+fn entry(row: &rusqlite::Row) -> rusqlite::Result<HistoryEntry> {
+    Ok(HistoryEntry {
+        id: row.get(0)?,
+        video_id: row.get(1)?,
+        title: row.get(2)?,
+        artist: row.get(3)?,
+        album: row.get(4)?,
+        duration: row.get(5)?,
+        started_at: row.get(6)?,
+        listened: row.get(7)?,
+        completed: row.get(8)?,
+        skipped: row.get(9)?,
+    })
+}
+
+/// Every play started in `[from, to)`, oldest first. Open bounds default to all time.
+pub fn entries(conn: &Connection, from: Option<i64>, to: Option<i64>) -> Result<Vec<HistoryEntry>, String> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT id, video_id, title, artist, album, duration, started_at, listened, completed, skipped
+             FROM plays WHERE started_at >= ?1 AND started_at < ?2 ORDER BY started_at, id",
+        )
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map(params![from.unwrap_or(i64::MIN), to.unwrap_or(i64::MAX)], entry)
+        .map_err(|e| e.to_string())?;
+    rows.collect::<Result<_, _>>().map_err(|e| e.to_string())
+}
+
+/// Plays started in `[from, to)` (unix seconds), newest first
+#[tauri::command]
+pub fn history_query(
+    state: State<'_, HistoryState>,
+    from: Option<i64>,
+    to: Option<i64>,
+    offset: Option<u32>,
+    limit: Option<u32>,
+) -> Result<HistoryPage, String> {
+    query(&state.0.lock().unwrap(), from, to, offset, limit)
+}
+
+fn query(
+    conn: &Connection,
+    from: Option<i64>,
+    to: Option<i64>,
+    offset: Option<u32>,
+    limit: Option<u32>,
+) -> Result<HistoryPage, String> {
+    let (from, to) = (from.unwrap_or(i64::MIN), to.unwrap_or(i64::MAX));
+    let limit = limit.unwrap_or(50).min(MAX_PAGE);
+
+    let total: i64 = conn
+        .query_row("SELECT COUNT(*) FROM plays WHERE started_at >= ?1 AND started_at < ?2", params![from, to], |row| {
+            row.get(0)
+        })
+        .map_err(|e| e.to_string())?;
+
+    let mut stmt = conn
+        .prepare(
+            "SELECT id, video_id, title, artist, album, duration, started_at, listened, completed, skipped
+             FROM plays WHERE started_at >= ?1 AND started_at < ?2
+             ORDER BY started_at DESC, id DESC LIMIT ?3 OFFSET ?4",
+        )
+        .map_err(|e| e.to_string())?;
+    let entries = stmt
+        .query_map(params![from, to, limit, offset.unwrap_or(0)], entry)
+        .map_err(|e| e.to_string())?
+        .collect::<Result<_, _>>()
+        .map_err(|e| e.to_string())?;
+
+    Ok(HistoryPage { entries, total: total as u64 })
+}
+
+/// Delete plays by id. Returns how many were removed.
+#[tauri::command]
+pub fn history_delete(state: State<'_, HistoryState>, ids: Vec<i64>) -> Result<usize, String> {
+    delete(&mut state.0.lock().unwrap(), &ids)
+}
+
+fn delete(conn: &mut Connection, ids: &[i64]) -> Result<usize, String> {
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    let mut removed = 0;
+    {
+        let mut stmt = tx.prepare("DELETE FROM plays WHERE id = ?1").map_err(|e| e.to_string())?;
+        for id in ids {
+            removed += stmt.execute(params![id]).map_err(|e| e.to_string())?;
+        }
+    }
+    tx.commit().map_err(|e| e.to_string())?;
+    Ok(removed)
+}
+
+/// Delete every play started in `[from, to)`; both bounds open means the whole history
+#[tauri::command]
+pub fn history_clear(state: State<'_, HistoryState>, from: Option<i64>, to: Option<i64>) -> Result<usize, String> {
+    clear(&state.0.lock().unwrap(), from, to)
+}
+
+fn clear(conn: &Connection, from: Option<i64>, to: Option<i64>) -> Result<usize, String> {
+    conn.execute(
+        "DELETE FROM plays WHERE started_at >= ?1 AND started_at < ?2",
+        params![from.unwrap_or(i64::MIN), to.unwrap_or(i64::MAX)],
+    )
+    .map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::now_playing::Track;
+
+    fn db() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        init(&conn).unwrap();
+        conn
+    }
+
+    fn play(video_id: &str, started_at: i64, listened: f64, position: f64) -> Play {
+        let track = Track {
+            video_id: video_id.to_string(),
+            title: format!("Title {}", video_id),
+            artist: "Artist".to_string(),
+            album: "Album".to_string(),
+            duration: 200.0,
+            ..Default::default()
+        };
+        Play { track, started_at, listened, position }
+    }
+
+    /// Ids of `entries`, in order
+    fn ids(entries: &[HistoryEntry]) -> Vec<&str> {
+        entries.iter().map(|e| e.video_id.as_str()).collect()
+    }
+
+    #[test]
+    fn schema_is_idempotent_and_round_trips_entries() {
+        let conn = db();
+        init(&conn).unwrap();
+        assert_eq!(insert(&conn, &play("a", 100, 200.0, 200.0), false).unwrap(), 1);
+        assert_eq!(insert(&conn, &play("b", 200, 30.0, 30.0), true).unwrap(), 1);
+
+        let all = entries(&conn, None, None).unwrap();
+        let a = &all[0];
+        assert_eq!(
+            (a.video_id.as_str(), a.title.as_str(), a.artist.as_str(), a.album.as_str()),
+            ("a", "Title a", "Artist", "Album")
+        );
+        assert_eq!((a.duration, a.started_at, a.listened, a.completed, a.skipped), (200.0, 100, 200.0, true, false));
+        assert_eq!((all[1].completed, all[1].skipped), (false, true));
+    }
+
+    #[test]
+    fn record_skips_plays_under_a_second() {
+        let conn = db();
+        assert_eq!(insert(&conn, &play("a", 100, 0.9, 0.9), true).unwrap(), 0);
+        assert_eq!(insert(&conn, &play("b", 100, 1.0, 1.0), true).unwrap(), 1);
+        assert_eq!(ids(&entries(&conn, None, None).unwrap()), ["b"]);
+    }
+
+    #[test]
+    fn entries_filter_a_half_open_range() {
+        let conn = db();
+        for (id, at) in [("c", 300), ("a", 100), ("b", 200), ("d", 400)] {
+            insert(&conn, &play(id, at, 10.0, 10.0), false).unwrap();
+        }
+        assert_eq!(ids(&entries(&conn, None, None).unwrap()), ["a", "b", "c", "d"]);
+        assert_eq!(ids(&entries(&conn, Some(200), Some(400)).unwrap()), ["b", "c"]);
+        assert_eq!(ids(&entries(&conn, Some(250), None).unwrap()), ["c", "d"]);
+        assert_eq!(ids(&entries(&conn, None, Some(100)).unwrap()), Vec::<&str>::new());
+
+        let page = query(&conn, Some(100), None, Some(1), Some(2)).unwrap();
+        assert_eq!((ids(&page.entries), page.total), (vec!["c", "b"], 4));
+    }
+
+    #[test]
+    fn delete_and_clear_remove_plays() {
+        let mut conn = db();
+        for (id, at) in [("a", 100), ("b", 200), ("c", 300), ("d", 400)] {
+            insert(&conn, &play(id, at, 10.0, 10.0), false).unwrap();
+        }
+        let b = entries(&conn, Some(200), Some(201)).unwrap()[0].id;
+        assert_eq!(delete(&mut conn, &[b, 9999]).unwrap(), 1);
+        assert_eq!(ids(&entries(&conn, None, None).unwrap()), ["a", "c", "d"]);
+
+        assert_eq!(clear(&conn, Some(300), None).unwrap(), 2);
+        assert_eq!(ids(&entries(&conn, None, None).unwrap()), ["a"]);
+        assert_eq!(clear(&conn, None, None).unwrap(), 1);
+        assert!(entries(&conn, None, None).unwrap().is_empty());
+    }
+}
@@ -0,0 +1,30 @@
+use serde_json::{json, Value};
+
+/// Must stay in sync with `YTM_CONTEXT` in `src/api/yt.ts`
+const CLIENT_VERSION: &str = "1.20260218.03.00";
+
+fn context() -> Value {
+    json!({
+        "client": {
+            "clientName": "WEB_REMIX",
+            "clientVersion": CLIENT_VERSION,
+        }
+    })
+}
+
+/// Call an innertube endpoint through the logged-in webview, filling in the client context
+pub async fn call(app: &tauri::AppHandle, endpoint: &str, mut body: Value) -> Result<Value, String> {
+    body["context"] = context();
+    crate::webview_request(app, endpoint, &body.to_string()).await
+}
+
+/// Flatten a `{ runs: [...] }` or `{ simpleText }` text object
+pub fn text(v: &Value) -> String {
+    if let Some(s) = v.as_str() {
+        return s.to_string();
+    }
+    if let Some(runs) = v["runs"].as_array() {
+        return runs.iter().filter_map(|r| r["text"].as_str()).collect();
+    }
+    v["simpleText"].as_str().unwrap_or_default().to_string()
+}
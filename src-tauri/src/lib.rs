@@ -2,10 +2,46 @@ use serde_json::{Value, json};
 use std::sync::Mutex;
 use tauri::{State, Emitter};
 use std::sync::Arc;
-use discord_rich_presence::{activity, DiscordIpc, DiscordIpcClient};
 use souvlaki::{MediaControlEvent, MediaControls, PlatformConfig};
 
-struct DiscordState(Mutex<Option<DiscordIpcClient>>);
+mod catalog;
+pub mod cli;
+mod control;
+mod csv;
+mod discord;
+mod history;
+mod innertube;
+mod links;
+mod listener;
+mod lyrics;
+mod matching;
+mod mpd;
+mod notifications;
+mod now_playing;
+mod plays;
+mod playlists;
+mod rating;
+mod remote;
+mod scrobble;
+mod settings;
+mod stats;
+#[cfg(desktop)]
+mod shortcuts;
+#[cfg(desktop)]
+mod tray;
+
+use discord::DiscordState;
+use links::LinksState;
+use lyrics::LyricsState;
+use notifications::NotificationState;
+use mpd::MpdState;
+use now_playing::{NowPlayingState, QueueState};
+use plays::PlaysState;
+use rating::RatingState;
+use remote::RemoteState;
+use scrobble::ScrobbleState;
+use settings::{SettingsState, SettingsWriter};
+
 struct MediaState(Mutex<Option<MediaControls>>);
 
 /// Percent-encode a string for safe embedding in JS
@@ -31,6 +67,8 @@ struct AppState {
     bridge_response: Arc<Mutex<Option<String>>>,
     /// Signal that a response is ready
     bridge_ready: Arc<Mutex<bool>>,
+    /// Serializes bridge round-trips, which share a single response slot
+    bridge_lock: tokio::sync::Mutex<()>,
 }
 
 /// Start a tiny HTTP server that receives responses from the webview
@@ -112,15 +150,26 @@ fn get_bridge_port(state: State<'_, AppState>) -> u16 {
 #[tauri::command]
 async fn ytm_webview_request(
     app: tauri::AppHandle,
-    state: State<'_, AppState>,
     endpoint: String,
     body_json: String,
+) -> Result<Value, String> {
+    webview_request(&app, &endpoint, &body_json).await
+}
+
+/// Innertube request through the logged-in webview, usable from Rust subsystems
+async fn webview_request(
+    app: &tauri::AppHandle,
+    endpoint: &str,
+    body_json: &str,
 ) -> Result<Value, String> {
     use tauri::Manager;
     
     let win = app.get_webview_window("ytm-login")
         .ok_or("Login window not found. Please sign in first.")?;
     
+    let timeout_secs = app.state::<SettingsState>().0.lock().unwrap().bridge.request_timeout_secs;
+    let state = app.state::<AppState>();
+    let _bridge = state.bridge_lock.lock().await;
     let port = state.bridge_port;
     
     // Reset bridge state
@@ -154,14 +203,14 @@ async fn ytm_webview_request(
         }})();
     "#,
         endpoint = endpoint,
-        body_encoded = urlencoding(&body_json),
+        body_encoded = urlencoding(body_json),
         port = port
     );
     
     win.eval(&js).map_err(|e| format!("eval error: {}", e))?;
     
     // Wait for response from bridge
-    for _ in 0..60 {
+    for _ in 0..timeout_secs * 4 {
         tokio::time::sleep(std::time::Duration::from_millis(250)).await;
         if *state.bridge_ready.lock().unwrap() {
             let resp = state.bridge_response.lock().unwrap().take()
@@ -182,7 +231,7 @@ async fn ytm_webview_request(
         }
     }
     
-    Err("Timeout waiting for webview response (15s)".to_string())
+    Err(format!("Timeout waiting for webview response ({}s)", timeout_secs))
 }
 
 /// Navigate webview and scrape DOM
@@ -198,6 +247,8 @@ async fn ytm_scrape(
     let win = app.get_webview_window("ytm-login")
         .ok_or("Login window not found. Please sign in first.")?;
     
+    let cfg = app.state::<SettingsState>().0.lock().unwrap().bridge.clone();
+    let _bridge = state.bridge_lock.lock().await;
     let port = state.bridge_port;
     
     // Navigate to the page
@@ -205,7 +256,7 @@ async fn ytm_scrape(
         .map_err(|e| format!("nav eval error: {}", e))?;
     
     // Wait for page to load
-    tokio::time::sleep(std::time::Duration::from_secs(4)).await;
+    tokio::time::sleep(std::time::Duration::from_millis(cfg.page_load_wait_ms)).await;
     
     // Reset bridge
     *state.bridge_response.lock().unwrap() = None;
@@ -234,7 +285,7 @@ async fn ytm_scrape(
     win.eval(&js).map_err(|e| format!("scrape eval error: {}", e))?;
     
     // Wait for response
-    for _ in 0..40 {
+    for _ in 0..cfg.scrape_timeout_secs * 4 {
         tokio::time::sleep(std::time::Duration::from_millis(250)).await;
         if *state.bridge_ready.lock().unwrap() {
             let resp = state.bridge_response.lock().unwrap().take()
@@ -254,7 +305,7 @@ async fn ytm_scrape(
         }
     }
     
-    Err("Timeout waiting for scraper (10s)".to_string())
+    Err(format!("Timeout waiting for scraper ({}s)", cfg.scrape_timeout_secs))
 }
 
 /// Open YouTube Music login window
@@ -413,6 +464,11 @@ async fn ytm_get_playback_state(
         None => return Ok(json!(null)),
     };
     
+    // Skip this poll rather than queue behind a slow request
+    let Ok(_bridge) = state.bridge_lock.try_lock() else {
+        return Ok(json!(null));
+    };
+    let timeout_ms = app.state::<SettingsState>().0.lock().unwrap().bridge.state_timeout_ms;
     let port = state.bridge_port;
     *state.bridge_response.lock().unwrap() = None;
     *state.bridge_ready.lock().unwrap() = false;
@@ -437,14 +493,20 @@ async fn ytm_get_playback_state(
     
     win.eval(&js).map_err(|e| e.to_string())?;
     
-    // Quick poll — 2 seconds max
-    for _ in 0..8 {
+    // Quick poll, 2 seconds by default
+    for _ in 0..timeout_ms.div_ceil(250) {
         tokio::time::sleep(std::time::Duration::from_millis(250)).await;
         if *state.bridge_ready.lock().unwrap() {
             let resp = state.bridge_response.lock().unwrap().take();
             *state.bridge_ready.lock().unwrap() = false;
             if let Some(r) = resp {
-                return serde_json::from_str(&r).map_err(|e| e.to_string());
+                let v: Value = serde_json::from_str(&r).map_err(|e| e.to_string())?;
+                lyrics::update_position(
+                    &app,
+                    v["current_time"].as_f64().unwrap_or(0.0),
+                    v["is_playing"].as_bool().unwrap_or(false),
+                );
+                return Ok(v);
             }
         }
     }
@@ -452,61 +514,6 @@ async fn ytm_get_playback_state(
     Ok(json!(null))
 }
 
-#[tauri::command]
-fn ytm_update_discord_rpc(
-    state: State<'_, DiscordState>,
-    title: String,
-    artist: String,
-    video_id: String,
-    thumb_url: String,
-    is_playing: bool,
-    current_time: f64,
-    duration: f64,
-) -> Result<(), String> {
-    let mut client_opt = state.0.lock().unwrap();
-    if client_opt.is_none() {
-        if let Ok(mut client) = DiscordIpcClient::new("1194717480627740753") {
-            if client.connect().is_ok() {
-                *client_opt = Some(client);
-            }
-        }
-    }
-
-    if let Some(client) = client_opt.as_mut() {
-        let large_img = if thumb_url.is_empty() { "icon".to_string() } else { thumb_url };
-
-        let mut act = activity::Activity::new()
-            .state(artist.as_str())
-            .assets(activity::Assets::new().large_image(large_img.as_str()).large_text("GoyMusic"));
-        
-        let listen_url = if video_id.is_empty() {
-            "https://music.youtube.com".to_string()
-        } else {
-            format!("https://music.youtube.com/watch?v={}", video_id)
-        };
-        
-        act = act.buttons(vec![
-            activity::Button::new("Listen", &listen_url),
-            activity::Button::new("Download", "https://github.com/lonestill/goymusic"),
-        ]);
-
-        let pause_details = if title.is_empty() { "Browsing...".to_string() } else { format!("Paused: {}", title) };
-
-        if is_playing && duration > 0.0 {
-            act = act.details(title.as_str());
-            let now = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).unwrap().as_secs() as i64;
-            let start = now - (current_time as i64);
-            let end = start + (duration as i64);
-            act = act.timestamps(activity::Timestamps::new().start(start).end(end));
-        } else {
-            act = act.details(&pause_details);
-        }
-
-        let _ = client.set_activity(act);
-    }
-    Ok(())
-}
-
 #[tauri::command]
 fn ytm_update_media_controls(
     state: State<'_, MediaState>,
@@ -534,46 +541,106 @@ fn ytm_update_media_controls(
     Ok(())
 }
 
+/// Release external integrations so nothing lingers after the process exits
+fn shutdown(app: &tauri::AppHandle) {
+    use tauri::Manager;
+    plays::finish_current(app);
+    settings::flush(app);
+    discord::shutdown(app);
+    if let Some(media) = app.try_state::<MediaState>() {
+        if let Some(mut controls) = media.0.lock().unwrap().take() {
+            let _ = controls.detach();
+        }
+    }
+}
+
+/// Exit for real: stop the hidden player webview and tear down integrations
+fn quit(app: &tauri::AppHandle) {
+    use tauri::Manager;
+    if let Some(player) = app.get_webview_window("ytm-login") {
+        let _ = player.destroy();
+    }
+    shutdown(app);
+    app.exit(0);
+}
+
 #[tauri::command]
-async fn fetch_lyrics(track_name: String, artist_name: String) -> Result<Value, String> {
-    let client = reqwest::Client::new();
-    let resp = client
-        .get("https://lrclib.net/api/search")
-        .query(&[("track_name", &track_name), ("artist_name", &artist_name)])
-        .header("User-Agent", "GoyMusic/1.0")
-        .send()
-        .await
-        .map_err(|e| e.to_string())?;
-    let body = resp.text().await.map_err(|e| e.to_string())?;
-    serde_json::from_str(&body).map_err(|e| e.to_string())
+fn app_quit(app: tauri::AppHandle) {
+    quit(&app);
 }
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
-    let (port, response, ready) = start_bridge_server();
-    
-    tauri::Builder::default()
+    let mut builder = tauri::Builder::default();
+
+    // Must be the first plugin so a second launch exits before starting anything
+    #[cfg(desktop)]
+    {
+        builder = builder.plugin(tauri_plugin_single_instance::init(|app, argv, _cwd| {
+            links::open(app, argv.get(1..).unwrap_or_default());
+            tray::show_main_window(app);
+        }));
+    }
+
+    builder
         .setup(|app| {
+            use tauri::Manager;
+            let (port, response, ready) = start_bridge_server();
+            app.manage(AppState {
+                bridge_port: port,
+                bridge_response: response,
+                bridge_ready: ready,
+                bridge_lock: tokio::sync::Mutex::new(()),
+            });
+
+            let initial = settings::load(app.handle());
+            settings::apply_window(app.handle(), &initial.window);
+            if initial.window.start_minimized {
+                if let Some(window) = app.get_webview_window("main") {
+                    let _ = window.hide();
+                }
+            }
+            let (remote_cfg, mpd_cfg) = (initial.remote.clone(), initial.mpd.clone());
+            app.manage(SettingsState(Mutex::new(initial)));
+            app.manage(history::open(app.handle()));
+            discord::spawn_idle_watcher(app.handle().clone());
+            lyrics::spawn_line_ticker(app.handle().clone());
+            scrobble::spawn_retry_loop(app.handle().clone());
+
+            // Links passed on the command line of this launch
+            let args: Vec<String> = std::env::args().skip(1).collect();
+            links::open(app.handle(), &args);
+            control::start_server(app.handle().clone());
+            let handle = app.handle().clone();
+            tauri::async_runtime::spawn(async move {
+                if let Err(e) = remote::restart(&handle, &remote_cfg).await {
+                    eprintln!("Remote control server failed: {}", e);
+                }
+                if let Err(e) = mpd::restart(&handle, &mpd_cfg).await {
+                    eprintln!("MPD server failed: {}", e);
+                }
+            });
+
+            #[cfg(desktop)]
+            {
+                use tauri_plugin_deep_link::DeepLinkExt;
+                // Installers register the scheme on their own; this covers portable and dev builds
+                #[cfg(any(windows, target_os = "linux"))]
+                if let Err(e) = app.deep_link().register_all() {
+                    eprintln!("Failed to register goymusic:// scheme: {}", e);
+                }
+                // macOS delivers deep links as events instead of arguments
+                let handle = app.handle().clone();
+                app.deep_link().on_open_url(move |event| {
+                    let urls: Vec<String> = event.urls().iter().map(|u| u.to_string()).collect();
+                    links::open(&handle, &urls);
+                });
+            }
+
             #[cfg(all(desktop, not(test)))]
             {
-                use tauri::Manager;
-                let _tray = tauri::tray::TrayIconBuilder::new()
-                    .icon(app.default_window_icon().unwrap().clone())
-                    .tooltip("GoyMusic")
-                    .on_tray_icon_event(|tray, event| {
-                        if let tauri::tray::TrayIconEvent::Click {
-                            button: tauri::tray::MouseButton::Left,
-                            button_state: tauri::tray::MouseButtonState::Up,
-                            ..
-                        } = event {
-                            let app = tray.app_handle();
-                            if let Some(window) = app.get_webview_window("main") {
-                                let _ = window.show();
-                                let _ = window.set_focus();
-                            }
-                        }
-                    })
-                    .build(app)?;
+                tray::build(app)?;
+                shortcuts::init(app)?;
 
                 // Initialize SMTC (Windows Media Controls)
                 let hwnd = app.get_webview_window("main").map(|w| w.hwnd().unwrap().0 as *mut std::ffi::c_void);
@@ -603,13 +670,19 @@ pub fn run() {
                 
                 app.manage(MediaState(Mutex::new(Some(controls))));
 
-                // Intercept close event to hide the window instead of killing it
+                // Closing the main window either hides it to the tray or quits, per settings
                 if let Some(window) = app.get_webview_window("main") {
                     let win_clone = window.clone();
+                    let app_handle = app.handle().clone();
                     window.on_window_event(move |event| {
                         if let tauri::WindowEvent::CloseRequested { api, .. } = event {
                             api.prevent_close();
-                            let _ = win_clone.hide();
+                            let close_to_tray = app_handle.state::<SettingsState>().0.lock().unwrap().window.close_to_tray;
+                            if close_to_tray {
+                                let _ = win_clone.hide();
+                            } else {
+                                quit(&app_handle);
+                            }
                         }
                     });
                 }
@@ -617,12 +690,21 @@ pub fn run() {
             Ok(())
         })
         .plugin(tauri_plugin_opener::init())
-        .manage(AppState {
-            bridge_port: port,
-            bridge_response: response,
-            bridge_ready: ready,
-        })
-        .manage(DiscordState(Mutex::new(None)))
+        .plugin(tauri_plugin_notification::init())
+        .plugin(tauri_plugin_deep_link::init())
+        .plugin(tauri_plugin_dialog::init())
+        .manage(DiscordState(Mutex::new(Default::default())))
+        .manage(LyricsState(Mutex::new(Default::default())))
+        .manage(NowPlayingState(Mutex::new(Default::default())))
+        .manage(QueueState(Mutex::new(Default::default())))
+        .manage(RemoteState::default())
+        .manage(MpdState::default())
+        .manage(NotificationState::default())
+        .manage(LinksState(Mutex::new(Default::default())))
+        .manage(PlaysState(Mutex::new(None)))
+        .manage(ScrobbleState::default())
+        .manage(RatingState::default())
+        .manage(SettingsWriter::default())
         .invoke_handler(tauri::generate_handler![
             get_bridge_port,
             open_ytm_login,
@@ -633,10 +715,48 @@ pub fn run() {
             ytm_seek,
             ytm_set_volume,
             ytm_get_playback_state,
-            ytm_update_discord_rpc,
+            discord::ytm_update_discord_rpc,
             ytm_update_media_controls,
-            fetch_lyrics,
+            now_playing::ytm_update_now_playing,
+            now_playing::ytm_update_queue,
+            lyrics::fetch_lyrics,
+            lyrics::refetch_lyrics,
+            lyrics::purge_lyrics,
+            lyrics::set_lyrics_offset,
+            lyrics::save_custom_lyrics,
+            lyrics::get_lyrics_override,
+            lyrics::export_lyrics,
+            settings::get_settings,
+            settings::update_settings,
+            app_quit,
+            links::take_pending_links,
+            scrobble::lastfm_begin_auth,
+            scrobble::lastfm_finish_auth,
+            history::history_query,
+            history::history_delete,
+            history::history_clear,
+            stats::stats_report,
+            stats::stats_export,
+            playlists::export_playlist,
+            playlists::import_playlist,
+            playlists::playlist_get,
+            playlists::playlist_create,
+            playlists::playlist_update,
+            playlists::playlist_delete,
+            playlists::playlist_add,
+            playlists::playlist_remove,
+            playlists::playlist_move,
+            rating::track_rating,
+            rating::rate_track,
+            rating::library_add,
+            rating::library_remove,
         ])
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .build(tauri::generate_context!())
+        .expect("error while running tauri application")
+        .run(|app, event| {
+            // Covers exits that bypass `quit`, e.g. OS logoff
+            if let tauri::RunEvent::Exit = event {
+                shutdown(app);
+            }
+        });
 }
@@ -0,0 +1,146 @@
+use serde::Serialize;
+use serde_json::json;
+use std::sync::Mutex;
+use tauri::{Emitter, Manager, State};
+
+use crate::innertube;
+use crate::now_playing::Track;
+
+/// Something to play, as named by a `goymusic://` or `music.youtube.com` URL
+pub enum Link {
+    Track(String),
+    /// A playlist, optionally starting at one of its tracks
+    Playlist { list: String, video: Option<String> },
+}
+
+/// A link resolved into something the frontend queue can use directly
+#[derive(Clone, Serialize)]
+#[serde(tag = "kind", rename_all = "lowercase")]
+pub enum OpenLink {
+    Track { track: Track },
+    Playlist {
+        #[serde(rename = "playlistId")]
+        playlist_id: String,
+        #[serde(rename = "videoId")]
+        video_id: Option<String>,
+    },
+}
+
+/// Links received before the frontend was ready to play them
+#[derive(Default)]
+pub struct LinksSession {
+    pending: Vec<Link>,
+    ready: bool,
+}
+
+pub struct LinksState(pub Mutex<LinksSession>);
+
+/// Recognize `goymusic://play?v=…`, `goymusic://playlist?list=…` and
+/// `https://music.youtube.com/watch?v=…` / `/playlist?list=…` URLs
+pub fn parse(arg: &str) -> Option<Link> {
+    let url = reqwest::Url::parse(arg.trim()).ok()?;
+    let param = |name: &str| {
+        url.query_pairs()
+            .find(|(k, _)| k == name)
+            .map(|(_, v)| v.into_owned())
+            .filter(|v| !v.is_empty())
+    };
+
+    let route = match url.scheme() {
+        "goymusic" => url.host_str().unwrap_or_default().to_string(),
+        "http" | "https" if url.host_str() == Some("music.youtube.com") => {
+            url.path().trim_matches('/').to_string()
+        }
+        _ => return None,
+    };
+
+    match route.as_str() {
+        "play" | "watch" => match param("list") {
+            Some(list) => Some(Link::Playlist { list, video: param("v") }),
+            None => param("v").map(Link::Track),
+        },
+        "playlist" => param("list").map(|list| Link::Playlist { list, video: None }),
+        _ => None,
+    }
+}
+
+/// Look up track metadata through the player endpoint
+async fn resolve_track(app: &tauri::AppHandle, video_id: &str) -> Result<Track, String> {
+    let resp = innertube::call(app, "player", json!({ "videoId": video_id })).await?;
+    let details = &resp["videoDetails"];
+    if details.is_null() {
+        return Err(format!("Video {} is unavailable", video_id));
+    }
+
+    Ok(Track {
+        video_id: video_id.to_string(),
+        title: details["title"].as_str().unwrap_or_default().to_string(),
+        // Auto-generated artist channels are named "<Artist> - Topic"
+        artist: details["author"].as_str().unwrap_or_default().trim_end_matches(" - Topic").to_string(),
+        album: String::new(),
+        thumb_url: details["thumbnail"]["thumbnails"]
+            .as_array()
+            .and_then(|t| t.last())
+            .and_then(|t| t["url"].as_str())
+            .unwrap_or_default()
+            .to_string(),
+        duration: details["lengthSeconds"].as_str().and_then(|s| s.parse().ok()).unwrap_or(0.0),
+    })
+}
+
+pub async fn resolve(app: &tauri::AppHandle, link: Link) -> Result<OpenLink, String> {
+    match link {
+        Link::Track(video_id) => Ok(OpenLink::Track { track: resolve_track(app, &video_id).await? }),
+        Link::Playlist { list, video } => Ok(OpenLink::Playlist { playlist_id: list, video_id: video }),
+    }
+}
+
+/// Route any recognized links among command line arguments into the player.
+/// Anything else is ignored.
+pub fn open(app: &tauri::AppHandle, args: &[String]) {
+    let links: Vec<Link> = args.iter().filter_map(|a| parse(a)).collect();
+    if links.is_empty() {
+        return;
+    }
+
+    {
+        let state = app.state::<LinksState>();
+        let mut session = state.0.lock().unwrap();
+        if !session.ready {
+            session.pending.extend(links);
+            return;
+        }
+    }
+
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        for link in links {
+            match resolve(&app, link).await {
+                Ok(open) => { let _ = app.emit("open-link", open); }
+                Err(e) => eprintln!("Failed to open link: {}", e),
+            }
+        }
+    });
+}
+
+/// Hand over links that arrived during startup. After this, new links are emitted as `open-link`.
+#[tauri::command]
+pub async fn take_pending_links(
+    app: tauri::AppHandle,
+    state: State<'_, LinksState>,
+) -> Result<Vec<OpenLink>, String> {
+    let pending = {
+        let mut session = state.0.lock().unwrap();
+        session.ready = true;
+        std::mem::take(&mut session.pending)
+    };
+
+    let mut resolved = Vec::new();
+    for link in pending {
+        match resolve(&app, link).await {
+            Ok(open) => resolved.push(open),
+            Err(e) => eprintln!("Failed to open link: {}", e),
+        }
+    }
+    Ok(resolved)
+}
@@ -0,0 +1,46 @@
+use std::future::Future;
+use tokio::net::TcpListener;
+use tokio::sync::watch;
+
+/// A TCP server that settings changes can stop and rebind. The lock is held across a restart so
+/// two can't race for the port.
+#[derive(Default)]
+pub struct Listener(tokio::sync::Mutex<Option<Server>>);
+
+/// The running server
+struct Server {
+    /// Dropping this stops the server and closes its connections
+    shutdown: watch::Sender<()>,
+    /// The accept loop, which owns the listener
+    task: tauri::async_runtime::JoinHandle<()>,
+}
+
+impl Listener {
+    /// Stop the running server, then bind `addr` and hand it to `serve`, or stay stopped if `addr`
+    /// is `None`. The old listener is closed first, so an error means the address itself is unusable.
+    pub async fn restart<F>(
+        &self,
+        name: &str,
+        addr: Option<(&str, u16)>,
+        serve: impl FnOnce(TcpListener, watch::Receiver<()>) -> F,
+    ) -> Result<(), String>
+    where
+        F: Future<Output = ()> + Send + 'static,
+    {
+        let mut server = self.0.lock().await;
+        if let Some(old) = server.take() {
+            drop(old.shutdown);
+            let _ = old.task.await;
+        }
+        let Some((host, port)) = addr else { return Ok(()) };
+
+        let listener = TcpListener::bind((host, port))
+            .await
+            .map_err(|e| format!("Can't listen on {}:{}: {}", host, port, e))?;
+        eprintln!("{} on {}:{}", name, host, port);
+        let (tx, rx) = watch::channel(());
+        let task = tauri::async_runtime::spawn(serve(listener, rx));
+        *server = Some(Server { shutdown: tx, task });
+        Ok(())
+    }
+}
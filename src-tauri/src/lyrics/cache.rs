@@ -0,0 +1,86 @@
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use tauri::Manager;
+
+use super::Lyrics;
+use crate::matching::{clean_title, normalize};
+
+/// A cached lookup. `lyrics: None` records that nothing was found.
+#[derive(Serialize, Deserialize)]
+pub struct CacheEntry {
+    /// Unix timestamp (seconds) of the lookup
+    pub fetched_at: u64,
+    pub lyrics: Option<Lyrics>,
+}
+
+impl CacheEntry {
+    /// Positive entries never expire; negative ones are retried after `negative_ttl_secs`
+    pub fn is_fresh(&self, negative_ttl_secs: u64) -> bool {
+        self.lyrics.is_some() || now_secs().saturating_sub(self.fetched_at) < negative_ttl_secs
+    }
+}
+
+fn now_secs() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+/// FNV-1a, stable across runs and Rust versions unlike `DefaultHasher`
+fn fnv1a(s: &str) -> u64 {
+    s.bytes().fold(0xcbf29ce484222325, |h, b| (h ^ b as u64).wrapping_mul(0x100000001b3))
+}
+
+/// Entry files for a track: one by video ID (if known) and one by normalized title/artist
+fn entry_paths(app: &tauri::AppHandle, video_id: Option<&str>, title: &str, artist: &str) -> Result<Vec<PathBuf>, String> {
+    let dir = app.path().app_data_dir().map_err(|e| e.to_string())?.join("lyrics");
+    let mut paths = Vec::new();
+
+    let id: String = video_id
+        .unwrap_or_default()
+        .chars()
+        .filter(|c| c.is_ascii_alphanumeric() || *c == '-' || *c == '_')
+        .collect();
+    if !id.is_empty() {
+        paths.push(dir.join(format!("v_{}.json", id)));
+    }
+
+    let key = format!("{}\u{1f}{}", normalize(&clean_title(title)), normalize(artist));
+    paths.push(dir.join(format!("t_{:016x}.json", fnv1a(&key))));
+    Ok(paths)
+}
+
+/// Look up a track, preferring the video ID entry over the title/artist one
+pub fn get(app: &tauri::AppHandle, video_id: Option<&str>, title: &str, artist: &str) -> Option<CacheEntry> {
+    entry_paths(app, video_id, title, artist)
+        .ok()?
+        .iter()
+        .filter_map(|p| std::fs::read_to_string(p).ok())
+        .find_map(|text| serde_json::from_str(&text).ok())
+}
+
+/// Store a lookup result under every key for the track
+pub fn put(app: &tauri::AppHandle, video_id: Option<&str>, title: &str, artist: &str, lyrics: Option<&Lyrics>) -> Result<(), String> {
+    let entry = CacheEntry { fetched_at: now_secs(), lyrics: lyrics.cloned() };
+    let text = serde_json::to_string(&entry).map_err(|e| e.to_string())?;
+    for path in entry_paths(app, video_id, title, artist)? {
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+        }
+        std::fs::write(&path, &text).map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
+/// Remove every cached entry for a track
+pub fn remove(app: &tauri::AppHandle, video_id: Option<&str>, title: &str, artist: &str) -> Result<(), String> {
+    for path in entry_paths(app, video_id, title, artist)? {
+        match std::fs::remove_file(&path) {
+            Ok(()) => {}
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => return Err(e.to_string()),
+        }
+    }
+    Ok(())
+}
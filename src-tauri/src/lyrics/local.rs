@@ -0,0 +1,62 @@
+use async_trait::async_trait;
+use std::path::PathBuf;
+
+use super::{lrc, provider::LyricsProvider, Lyrics, TrackQuery};
+use crate::matching::{clean_title, similarity};
+
+/// Minimum file name similarity to accept a local file
+const MIN_SCORE: f64 = 0.8;
+
+/// `.lrc` files in a user-chosen folder, matched by `Artist - Title.lrc` or `Title.lrc`
+pub struct LocalFiles {
+    pub folder: PathBuf,
+}
+
+impl LocalFiles {
+    fn best_match(&self, track: &TrackQuery) -> Option<(f64, PathBuf)> {
+        let title = clean_title(&track.track_name);
+        let full = format!("{} - {}", track.artist_name, title);
+
+        std::fs::read_dir(&self.folder)
+            .ok()?
+            .filter_map(|entry| entry.ok().map(|e| e.path()))
+            .filter(|path| path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("lrc")))
+            .filter_map(|path| {
+                let stem = path.file_stem()?.to_string_lossy().to_string();
+                let score = similarity(&stem, &full).max(similarity(&stem, &title) * 0.9);
+                Some((score, path))
+            })
+            .filter(|(score, _)| *score >= MIN_SCORE)
+            .max_by(|a, b| a.0.total_cmp(&b.0))
+    }
+}
+
+#[async_trait]
+impl LyricsProvider for LocalFiles {
+    fn name(&self) -> &'static str {
+        "local"
+    }
+
+    async fn fetch(&self, _app: &tauri::AppHandle, track: &TrackQuery) -> Result<Option<Lyrics>, String> {
+        let Some((score, path)) = self.best_match(track) else {
+            return Ok(None);
+        };
+        let text = std::fs::read_to_string(&path).map_err(|e| format!("{}: {}", path.display(), e))?;
+        let lines = lrc::parse(&text);
+
+        // Files without timestamps are treated as plain lyrics
+        let plain = if lines.is_empty() {
+            text.trim().to_string()
+        } else {
+            lines.iter().map(|l| l.text.as_str()).collect::<Vec<_>>().join("\n")
+        };
+
+        Ok(Some(Lyrics {
+            lines,
+            plain,
+            instrumental: false,
+            confidence: score,
+            source: self.name().to_string(),
+        }))
+    }
+}
@@ -0,0 +1,164 @@
+use super::{LyricLine, LyricWord};
+
+/// Parse a `[mm:ss.xx]` / `<mm:ss.xx>` timestamp body into seconds
+fn parse_timestamp(s: &str) -> Option<f64> {
+    let (min, rest) = s.split_once(':')?;
+    let min: u32 = min.trim().parse().ok()?;
+    // Some files use `mm:ss:xx` instead of `mm:ss.xx`
+    let rest = rest.replacen(':', ".", 1);
+    let sec: f64 = rest.trim().parse().ok()?;
+    if !(0.0..60.0).contains(&sec) {
+        return None;
+    }
+    Some(min as f64 * 60.0 + sec)
+}
+
+/// Split enhanced LRC text (`<00:01.00>Hello <00:01.50>world`) into words.
+/// Returns the plain text with tags removed and the timed words, if any.
+fn parse_words(text: &str) -> (String, Vec<LyricWord>) {
+    let mut plain = String::new();
+    let mut words: Vec<LyricWord> = Vec::new();
+    let mut rest = text;
+
+    while let Some(open) = rest.find('<') {
+        let Some(close) = rest[open..].find('>').map(|i| open + i) else { break };
+        let Some(time) = parse_timestamp(&rest[open + 1..close]) else {
+            // Not a timestamp, keep it as literal text
+            plain.push_str(&rest[..=close]);
+            if let Some(w) = words.last_mut() {
+                w.text.push_str(&rest[..=close]);
+            }
+            rest = &rest[close + 1..];
+            continue;
+        };
+
+        let before = &rest[..open];
+        plain.push_str(before);
+        if let Some(w) = words.last_mut() {
+            w.text.push_str(before);
+        }
+        words.push(LyricWord { time, text: String::new() });
+        rest = &rest[close + 1..];
+    }
+
+    plain.push_str(rest);
+    if let Some(w) = words.last_mut() {
+        w.text.push_str(rest);
+    }
+
+    // A trailing tag marks the end of the last word and carries no text
+    words.retain(|w| !w.text.is_empty());
+    (plain.trim().to_string(), words)
+}
+
+/// Parse an LRC document into time-sorted lines.
+///
+/// Handles repeated timestamps (`[00:10.00][01:20.00]Chorus`), the `[offset:±ms]`
+/// header and enhanced word-level `<mm:ss.xx>` tags. Metadata and untimed lines are skipped.
+pub fn parse(lrc: &str) -> Vec<LyricLine> {
+    let mut offset = 0.0;
+    let mut lines = Vec::new();
+
+    for raw in lrc.lines() {
+        let mut rest = raw.trim();
+        let mut times = Vec::new();
+
+        while rest.starts_with('[') {
+            let Some(close) = rest.find(']') else { break };
+            let tag = &rest[1..close];
+            if let Some(t) = parse_timestamp(tag) {
+                times.push(t);
+            } else if let Some((key, value)) = tag.split_once(':') {
+                if key.trim().eq_ignore_ascii_case("offset") {
+                    offset = value.trim().parse::<f64>().unwrap_or(0.0) / 1000.0;
+                }
+            }
+            rest = &rest[close + 1..];
+        }
+
+        if times.is_empty() {
+            continue;
+        }
+
+        let (text, words) = parse_words(rest);
+        for t in times {
+            lines.push(LyricLine { time: t, text: text.clone(), words: words.clone(), romanized: None });
+        }
+    }
+
+    // A positive offset means lyrics should appear earlier
+    if offset != 0.0 {
+        for line in &mut lines {
+            line.time = (line.time - offset).max(0.0);
+            for w in &mut line.words {
+                w.time = (w.time - offset).max(0.0);
+            }
+        }
+    }
+
+    lines.sort_by(|a, b| a.time.total_cmp(&b.time));
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn times(lines: &[LyricLine]) -> Vec<f64> {
+        lines.iter().map(|l| l.time).collect()
+    }
+
+    #[test]
+    fn repeated_timestamps_become_sorted_lines() {
+        let lines = parse("[01:20.00][00:10.00]Chorus\n[00:30.00]Verse\n");
+        assert_eq!(times(&lines), [10.0, 30.0, 80.0]);
+        let texts: Vec<&str> = lines.iter().map(|l| l.text.as_str()).collect();
+        assert_eq!(texts, ["Chorus", "Verse", "Chorus"]);
+    }
+
+    #[test]
+    fn out_of_order_lines_are_sorted() {
+        let lines = parse("[00:05.00]c\n[00:01.00]a\n[00:03.00]b");
+        assert_eq!(times(&lines), [1.0, 3.0, 5.0]);
+    }
+
+    #[test]
+    fn accepts_colon_separated_hundredths() {
+        assert_eq!(times(&parse("[00:12:50]x")), [12.5]);
+    }
+
+    #[test]
+    fn offset_shifts_lines_and_words() {
+        let lines = parse("[offset:+500]\n[00:10.00]<00:10.00>A <00:11.00>B");
+        assert_eq!(lines[0].time, 9.5);
+        assert_eq!(lines[0].words.iter().map(|w| w.time).collect::<Vec<_>>(), [9.5, 10.5]);
+
+        // Negative offsets delay, and nothing moves before the start
+        assert_eq!(times(&parse("[offset:-1000]\n[00:10.00]A")), [11.0]);
+        assert_eq!(times(&parse("[offset:2000]\n[00:01.00]A")), [0.0]);
+    }
+
+    #[test]
+    fn enhanced_word_tags() {
+        let lines = parse("[00:01.00]<00:01.00>Hello <00:01.50>world<00:02.00>");
+        assert_eq!(lines[0].text, "Hello world");
+        assert_eq!(
+            lines[0].words,
+            [LyricWord { time: 1.0, text: "Hello ".to_string() }, LyricWord { time: 1.5, text: "world".to_string() }]
+        );
+    }
+
+    #[test]
+    fn malformed_tags_are_kept_or_skipped() {
+        // Angle brackets that aren't timestamps stay in the text
+        let lines = parse("[00:01.00]a <b> c");
+        assert_eq!(lines[0].text, "a <b> c");
+        assert!(lines[0].words.is_empty());
+
+        // Metadata, invalid or unclosed timestamps and untimed lines produce nothing
+        assert!(parse("[ar:Someone]\n[99:75.00]x\n[00:01.00 unclosed\nno time\n[xx:yy]z").is_empty());
+
+        let lines = parse("[00:02.00]<00:02.00>open <00:03");
+        assert_eq!(lines[0].text, "open <00:03");
+    }
+}
@@ -0,0 +1,129 @@
+use async_trait::async_trait;
+use serde::Deserialize;
+
+use super::{lrc, provider::LyricsProvider, Lyrics, TrackQuery};
+use crate::matching::{self, clean_title, TrackFields};
+
+/// lrclib.net, a free community database of synced lyrics
+pub struct Lrclib {
+    /// API root, e.g. `https://lrclib.net/api`
+    pub base_url: String,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default, rename_all = "camelCase")]
+struct LrclibRecord {
+    track_name: String,
+    artist_name: String,
+    album_name: Option<String>,
+    duration: Option<f64>,
+    instrumental: bool,
+    plain_lyrics: Option<String>,
+    synced_lyrics: Option<String>,
+}
+
+impl LrclibRecord {
+    fn into_lyrics(self, confidence: f64) -> Lyrics {
+        Lyrics {
+            lines: self.synced_lyrics.as_deref().map(lrc::parse).unwrap_or_default(),
+            plain: self.plain_lyrics.unwrap_or_default(),
+            instrumental: self.instrumental,
+            confidence,
+            source: "lrclib".to_string(),
+        }
+    }
+
+    fn has_synced(&self) -> bool {
+        self.synced_lyrics.as_deref().is_some_and(|l| !l.is_empty())
+    }
+}
+
+fn confidence(rec: &LrclibRecord, title: &str, artist: &str, album: Option<&str>, duration: Option<f64>) -> f64 {
+    matching::confidence(
+        &TrackFields { title, artist, album: album.unwrap_or_default(), duration: duration.unwrap_or(0.0) },
+        &TrackFields {
+            title: &rec.track_name,
+            artist: &rec.artist_name,
+            album: rec.album_name.as_deref().unwrap_or_default(),
+            duration: rec.duration.unwrap_or(0.0),
+        },
+    )
+}
+
+/// Exact lookup by signature. Returns `None` when LRCLIB has no such track.
+async fn get(base_url: &str, title: &str, artist: &str, album: Option<&str>, duration: f64) -> Result<Option<LrclibRecord>, String> {
+    let duration = (duration.round() as u64).to_string();
+    let mut query = vec![("track_name", title), ("artist_name", artist), ("duration", duration.as_str())];
+    if let Some(album) = album.filter(|a| !a.is_empty()) {
+        query.push(("album_name", album));
+    }
+
+    let resp = reqwest::Client::new()
+        .get(format!("{}/get", base_url))
+        .query(&query)
+        .header("User-Agent", "GoyMusic/1.0")
+        .send()
+        .await
+        .map_err(|e| e.to_string())?;
+    if resp.status() == reqwest::StatusCode::NOT_FOUND {
+        return Ok(None);
+    }
+    let body = resp.text().await.map_err(|e| e.to_string())?;
+    serde_json::from_str(&body).map(Some).map_err(|e| e.to_string())
+}
+
+async fn search(base_url: &str, title: &str, artist: &str) -> Result<Vec<LrclibRecord>, String> {
+    let resp = reqwest::Client::new()
+        .get(format!("{}/search", base_url))
+        .query(&[("track_name", title), ("artist_name", artist)])
+        .header("User-Agent", "GoyMusic/1.0")
+        .send()
+        .await
+        .map_err(|e| e.to_string())?;
+    let body = resp.text().await.map_err(|e| e.to_string())?;
+    serde_json::from_str(&body).map_err(|e| e.to_string())
+}
+
+#[async_trait]
+impl LyricsProvider for Lrclib {
+    fn name(&self) -> &'static str {
+        "lrclib"
+    }
+
+    /// Exact `/api/get` first when the duration is known, then a fuzzy search
+    async fn fetch(&self, _app: &tauri::AppHandle, track: &TrackQuery) -> Result<Option<Lyrics>, String> {
+        let (title, artist) = (track.track_name.as_str(), track.artist_name.as_str());
+        let (album, duration) = (track.album_name.as_deref(), track.duration);
+        let cleaned = clean_title(title);
+        let base_url = self.base_url.trim_end_matches('/');
+
+        if let Some(d) = duration.filter(|d| *d > 0.0) {
+            match get(base_url, &cleaned, artist, album, d).await {
+                Ok(Some(rec)) => {
+                    // An exact hit on the server's own matching earns a small bonus, not a floor
+                    let score = (confidence(&rec, title, artist, album, duration) + 0.1).min(1.0);
+                    return Ok(Some(rec.into_lyrics(score)));
+                }
+                Ok(None) => {}
+                Err(e) => eprintln!("LRCLIB get failed, falling back to search: {}", e),
+            }
+        }
+
+        let mut records = search(base_url, &cleaned, artist).await?;
+        if records.is_empty() && cleaned != title {
+            records = search(base_url, title, artist).await?;
+        }
+
+        let best = records
+            .into_iter()
+            .map(|rec| {
+                let score = confidence(&rec, title, artist, album, duration);
+                // Prefer results we can sync when scores are close
+                let rank = score + if rec.has_synced() { 0.05 } else { 0.0 };
+                (rank, score, rec)
+            })
+            .max_by(|a, b| a.0.total_cmp(&b.0));
+
+        Ok(best.map(|(_, score, rec)| rec.into_lyrics(score)))
+    }
+}
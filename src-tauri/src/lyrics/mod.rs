@@ -0,0 +1,372 @@
+use serde::{Deserialize, Serialize};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tauri::{Emitter, Manager, State};
+
+use crate::settings::SettingsState;
+
+mod cache;
+mod local;
+mod lrc;
+mod lrclib;
+mod overrides;
+mod provider;
+mod romanize;
+mod ytmusic;
+
+/// A single timed word from enhanced LRC
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct LyricWord {
+    pub time: f64,
+    pub text: String,
+}
+
+/// A synced lyric line, `time` in seconds from track start
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct LyricLine {
+    pub time: f64,
+    pub text: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub words: Vec<LyricWord>,
+    /// Latin transliteration for non-Latin scripts, if enabled
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub romanized: Option<String>,
+}
+
+/// Parsed lyrics for a track. `lines` is empty when only plain lyrics exist.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct Lyrics {
+    pub lines: Vec<LyricLine>,
+    pub plain: String,
+    pub instrumental: bool,
+    /// How sure we are that these lyrics belong to the requested track, `0.0..=1.0`
+    pub confidence: f64,
+    /// Name of the provider that supplied them
+    #[serde(default)]
+    pub source: String,
+}
+
+/// Payload of the `lyrics-line` event
+#[derive(Clone, Serialize)]
+struct LyricLineEvent {
+    index: Option<usize>,
+    line: Option<LyricLine>,
+}
+
+#[derive(Default)]
+pub struct LyricsSession {
+    /// Incremented on every fetch so stale results are dropped
+    fetch_seq: u64,
+    /// `TrackQuery::key` of the track whose lines are active
+    active_key: String,
+    lines: Vec<LyricLine>,
+    /// Last playback position reported by the webview and when it was received
+    position: f64,
+    position_at: Option<Instant>,
+    is_playing: bool,
+    current_line: Option<usize>,
+}
+
+impl LyricsSession {
+    fn estimated_position(&self) -> f64 {
+        match self.position_at {
+            Some(at) if self.is_playing => self.position + at.elapsed().as_secs_f64(),
+            _ => self.position,
+        }
+    }
+}
+
+pub struct LyricsState(pub Mutex<LyricsSession>);
+
+/// Index of the line active at `time`, i.e. the last one that has started
+fn line_at(lines: &[LyricLine], time: f64) -> Option<usize> {
+    lines.partition_point(|l| l.time <= time).checked_sub(1)
+}
+
+/// Record the latest playback position so line events stay in sync
+pub fn update_position(app: &tauri::AppHandle, current_time: f64, is_playing: bool) {
+    let state = app.state::<LyricsState>();
+    let mut session = state.0.lock().unwrap();
+    session.position = current_time;
+    session.position_at = Some(Instant::now());
+    session.is_playing = is_playing;
+}
+
+/// Emit `lyrics-line` whenever the active line changes, interpolating between position updates
+pub fn spawn_line_ticker(app: tauri::AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        loop {
+            tokio::time::sleep(Duration::from_millis(100)).await;
+            let event = {
+                let state = app.state::<LyricsState>();
+                let mut session = state.0.lock().unwrap();
+                let index = line_at(&session.lines, session.estimated_position());
+                if index == session.current_line {
+                    continue;
+                }
+                session.current_line = index;
+                LyricLineEvent {
+                    index,
+                    line: index.map(|i| session.lines[i].clone()),
+                }
+            };
+            let _ = app.emit("lyrics-line", event);
+        }
+    });
+}
+
+/// Track identity used for lookups and cache keys
+pub struct TrackQuery {
+    pub track_name: String,
+    pub artist_name: String,
+    pub album_name: Option<String>,
+    pub duration: Option<f64>,
+    pub video_id: Option<String>,
+}
+
+impl TrackQuery {
+    /// Stable identity: the video ID when known, otherwise normalized title and artist
+    pub fn key(&self) -> String {
+        match self.video_id.as_deref().filter(|id| !id.is_empty()) {
+            Some(id) => id.to_string(),
+            None => format!(
+                "{}\u{1f}{}",
+                crate::matching::normalize(&crate::matching::clean_title(&self.track_name)),
+                crate::matching::normalize(&self.artist_name),
+            ),
+        }
+    }
+}
+
+/// Ask each configured provider in turn. Synced lyrics win; plain lyrics are kept
+/// as a fallback in case a lower-priority provider has synced ones.
+async fn from_providers(app: &tauri::AppHandle, track: &TrackQuery) -> Result<Option<Lyrics>, String> {
+    let cfg = app.state::<SettingsState>().0.lock().unwrap().lyrics.clone();
+    let mut plain_only = None;
+    let mut last_err = None;
+
+    for p in provider::build(&cfg) {
+        match p.fetch(app, track).await {
+            Ok(Some(lyrics)) if !lyrics.lines.is_empty() || lyrics.instrumental => return Ok(Some(lyrics)),
+            Ok(Some(lyrics)) => {
+                if plain_only.is_none() && !lyrics.plain.trim().is_empty() {
+                    plain_only = Some(lyrics);
+                }
+            }
+            Ok(None) => {}
+            Err(e) => {
+                eprintln!("Lyrics provider {} failed: {}", p.name(), e);
+                last_err = Some(e);
+            }
+        }
+    }
+
+    // Don't report "no lyrics" (and get negatively cached) if a provider was unreachable
+    match (plain_only, last_err) {
+        (Some(lyrics), _) => Ok(Some(lyrics)),
+        (None, Some(e)) => Err(e),
+        (None, None) => Ok(None),
+    }
+}
+
+/// Resolve lyrics from the on-disk cache or the providers, falling back to stale cache when offline
+async fn resolve(
+    app: &tauri::AppHandle,
+    track: &TrackQuery,
+    use_cache: bool,
+) -> Result<Option<Lyrics>, String> {
+    let negative_ttl = app.state::<SettingsState>().0.lock().unwrap().lyrics.negative_cache_hours * 3600;
+    let video_id = track.video_id.as_deref();
+    let cached = cache::get(app, video_id, &track.track_name, &track.artist_name);
+
+    if use_cache {
+        if let Some(entry) = cached.as_ref().filter(|e| e.is_fresh(negative_ttl)) {
+            return Ok(entry.lyrics.clone());
+        }
+    }
+
+    match from_providers(app, track).await {
+        Ok(lyrics) => {
+            if let Err(e) = cache::put(app, video_id, &track.track_name, &track.artist_name, lyrics.as_ref()) {
+                eprintln!("Failed to cache lyrics: {}", e);
+            }
+            Ok(lyrics)
+        }
+        Err(e) => match cached {
+            Some(entry) => Ok(entry.lyrics),
+            None => Err(e),
+        },
+    }
+}
+
+/// Resolve lyrics, then apply the user's custom LRC, timing offset and romanization
+async fn lookup(app: &tauri::AppHandle, track: &TrackQuery, use_cache: bool) -> Result<Option<Lyrics>, String> {
+    let ov = overrides::get(app, track).unwrap_or_default();
+    let lyrics = match ov.lrc.as_deref() {
+        // User-supplied lyrics take priority over every provider
+        Some(body) => Some(overrides::custom_lyrics(body)),
+        None => resolve(app, track, use_cache).await?,
+    };
+
+    let romanize_cfg = app.state::<SettingsState>().0.lock().unwrap().lyrics.romanize.clone();
+    Ok(lyrics.map(|l| {
+        let mut l = overrides::apply_offset(l, ov.offset_ms);
+        for line in &mut l.lines {
+            line.romanized = romanize::romanize(&line.text, &romanize_cfg);
+        }
+        l
+    }))
+}
+
+/// Look lyrics up and make them the active set for `lyrics-line` events
+async fn load_active(
+    app: &tauri::AppHandle,
+    state: &LyricsState,
+    track: &TrackQuery,
+    use_cache: bool,
+) -> Result<Option<Lyrics>, String> {
+    let seq = {
+        let mut session = state.0.lock().unwrap();
+        session.fetch_seq += 1;
+        session.active_key = track.key();
+        session.lines.clear();
+        session.fetch_seq
+    };
+
+    let lyrics = lookup(app, track, use_cache).await?;
+
+    let mut session = state.0.lock().unwrap();
+    if session.fetch_seq == seq {
+        session.lines = lyrics.as_ref().map(|l| l.lines.clone()).unwrap_or_default();
+    }
+    Ok(lyrics)
+}
+
+/// Re-run the lookup after an override change, refreshing live lines if the track is playing
+async fn reload_after_edit(app: &tauri::AppHandle, state: &LyricsState, track: &TrackQuery) -> Result<Option<Lyrics>, String> {
+    let is_active = state.0.lock().unwrap().active_key == track.key();
+    if is_active {
+        load_active(app, state, track, true).await
+    } else {
+        lookup(app, track, true).await
+    }
+}
+
+/// Find and parse lyrics for a track, and make them the active set for `lyrics-line` events
+#[tauri::command]
+pub async fn fetch_lyrics(
+    app: tauri::AppHandle,
+    state: State<'_, LyricsState>,
+    track_name: String,
+    artist_name: String,
+    album_name: Option<String>,
+    duration: Option<f64>,
+    video_id: Option<String>,
+) -> Result<Option<Lyrics>, String> {
+    let track = TrackQuery { track_name, artist_name, album_name, duration, video_id };
+    load_active(&app, &state, &track, true).await
+}
+
+/// Bypass the cache and look the track up again, replacing its cache entry
+#[tauri::command]
+pub async fn refetch_lyrics(
+    app: tauri::AppHandle,
+    state: State<'_, LyricsState>,
+    track_name: String,
+    artist_name: String,
+    album_name: Option<String>,
+    duration: Option<f64>,
+    video_id: Option<String>,
+) -> Result<Option<Lyrics>, String> {
+    let track = TrackQuery { track_name, artist_name, album_name, duration, video_id };
+    load_active(&app, &state, &track, false).await
+}
+
+/// Drop a single track from the lyrics cache
+#[tauri::command]
+pub fn purge_lyrics(
+    app: tauri::AppHandle,
+    track_name: String,
+    artist_name: String,
+    video_id: Option<String>,
+) -> Result<(), String> {
+    cache::remove(&app, video_id.as_deref(), &track_name, &artist_name)
+}
+
+fn bare_track(track_name: String, artist_name: String, video_id: Option<String>) -> TrackQuery {
+    TrackQuery { track_name, artist_name, album_name: None, duration: None, video_id }
+}
+
+/// Set a per-track timing offset in milliseconds (positive shows lines earlier)
+#[tauri::command]
+pub async fn set_lyrics_offset(
+    app: tauri::AppHandle,
+    state: State<'_, LyricsState>,
+    track_name: String,
+    artist_name: String,
+    video_id: Option<String>,
+    offset_ms: i64,
+) -> Result<Option<Lyrics>, String> {
+    let track = bare_track(track_name, artist_name, video_id);
+    overrides::update(&app, &track, |ov| ov.offset_ms = offset_ms)?;
+    reload_after_edit(&app, &state, &track).await
+}
+
+/// Save a user-edited LRC body for a track, or remove it with `lrc: null`
+#[tauri::command]
+pub async fn save_custom_lyrics(
+    app: tauri::AppHandle,
+    state: State<'_, LyricsState>,
+    track_name: String,
+    artist_name: String,
+    video_id: Option<String>,
+    lrc: Option<String>,
+) -> Result<Option<Lyrics>, String> {
+    let track = bare_track(track_name, artist_name, video_id);
+    let lrc = lrc.filter(|body| !body.trim().is_empty());
+    overrides::update(&app, &track, |ov| ov.lrc = lrc)?;
+    reload_after_edit(&app, &state, &track).await
+}
+
+/// Current offset and custom LRC for a track, for the editor
+#[tauri::command]
+pub fn get_lyrics_override(
+    app: tauri::AppHandle,
+    track_name: String,
+    artist_name: String,
+    video_id: Option<String>,
+) -> overrides::LyricsOverride {
+    let track = bare_track(track_name, artist_name, video_id);
+    overrides::get(&app, &track).unwrap_or_default()
+}
+
+/// Write the track's lyrics, with offsets applied, to a standard `.lrc` file
+#[tauri::command]
+pub async fn export_lyrics(
+    app: tauri::AppHandle,
+    track_name: String,
+    artist_name: String,
+    album_name: Option<String>,
+    duration: Option<f64>,
+    video_id: Option<String>,
+    path: String,
+) -> Result<(), String> {
+    let track = TrackQuery { track_name, artist_name, album_name, duration, video_id };
+    let lyrics = lookup(&app, &track, true).await?.ok_or("No lyrics to export")?;
+    std::fs::write(&path, overrides::to_lrc(&lyrics, &track)).map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn line_at_finds_the_last_started_line() {
+        let lines = lrc::parse("[00:10.00]a\n[00:20.00]b\n[00:30.00]c");
+        assert_eq!(line_at(&lines, 5.0), None);
+        assert_eq!(line_at(&lines, 10.0), Some(0));
+        assert_eq!(line_at(&lines, 25.0), Some(1));
+        assert_eq!(line_at(&lines, 300.0), Some(2));
+        assert_eq!(line_at(&[], 10.0), None);
+    }
+}
@@ -0,0 +1,108 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use tauri::Manager;
+
+use super::{lrc, Lyrics, TrackQuery};
+
+/// User adjustments for one track
+#[derive(Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LyricsOverride {
+    /// Shift in milliseconds; positive shows lines earlier, like the LRC `[offset:]` tag
+    pub offset_ms: i64,
+    /// User-supplied LRC (or plain text) that replaces provider results
+    pub lrc: Option<String>,
+}
+
+fn overrides_path(app: &tauri::AppHandle) -> Result<PathBuf, String> {
+    let dir = app.path().app_data_dir().map_err(|e| e.to_string())?;
+    Ok(dir.join("lyrics").join("overrides.json"))
+}
+
+fn load_all(app: &tauri::AppHandle) -> HashMap<String, LyricsOverride> {
+    overrides_path(app)
+        .ok()
+        .and_then(|p| std::fs::read_to_string(p).ok())
+        .and_then(|text| serde_json::from_str(&text).ok())
+        .unwrap_or_default()
+}
+
+fn save_all(app: &tauri::AppHandle, all: &HashMap<String, LyricsOverride>) -> Result<(), String> {
+    let path = overrides_path(app)?;
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+    }
+    let text = serde_json::to_string_pretty(all).map_err(|e| e.to_string())?;
+    std::fs::write(&path, text).map_err(|e| e.to_string())
+}
+
+pub fn get(app: &tauri::AppHandle, track: &TrackQuery) -> Option<LyricsOverride> {
+    load_all(app).remove(&track.key())
+}
+
+/// Modify a track's override in place, dropping it once it no longer changes anything
+pub fn update(app: &tauri::AppHandle, track: &TrackQuery, f: impl FnOnce(&mut LyricsOverride)) -> Result<(), String> {
+    let mut all = load_all(app);
+    let key = track.key();
+    let entry = all.entry(key.clone()).or_default();
+    f(entry);
+    if entry.offset_ms == 0 && entry.lrc.is_none() {
+        all.remove(&key);
+    }
+    save_all(app, &all)
+}
+
+/// Lyrics built from a user-supplied LRC body
+pub fn custom_lyrics(body: &str) -> Lyrics {
+    let lines = lrc::parse(body);
+    let plain = if lines.is_empty() {
+        body.trim().to_string()
+    } else {
+        lines.iter().map(|l| l.text.as_str()).collect::<Vec<_>>().join("\n")
+    };
+    Lyrics { lines, plain, instrumental: false, confidence: 1.0, source: "user".to_string() }
+}
+
+pub fn apply_offset(mut lyrics: Lyrics, offset_ms: i64) -> Lyrics {
+    if offset_ms != 0 {
+        let shift = offset_ms as f64 / 1000.0;
+        for line in &mut lyrics.lines {
+            line.time = (line.time - shift).max(0.0);
+            for w in &mut line.words {
+                w.time = (w.time - shift).max(0.0);
+            }
+        }
+    }
+    lyrics
+}
+
+fn format_timestamp(time: f64) -> String {
+    let centis = (time * 100.0).round() as u64;
+    format!("{:02}:{:02}.{:02}", centis / 6000, centis / 100 % 60, centis % 100)
+}
+
+/// Serialize synced lyrics as a standard (non-enhanced) LRC document
+pub fn to_lrc(lyrics: &Lyrics, track: &TrackQuery) -> String {
+    let mut out = String::new();
+    out.push_str(&format!("[ti:{}]\n[ar:{}]\n", track.track_name, track.artist_name));
+    if let Some(album) = track.album_name.as_deref().filter(|a| !a.is_empty()) {
+        out.push_str(&format!("[al:{}]\n", album));
+    }
+    if let Some(d) = track.duration.filter(|d| *d > 0.0) {
+        let secs = d.round() as u64;
+        out.push_str(&format!("[length:{:02}:{:02}]\n", secs / 60, secs % 60));
+    }
+    out.push_str("[re:GoyMusic]\n");
+
+    if lyrics.lines.is_empty() {
+        // No timing available, export the plain text as-is
+        out.push_str(&lyrics.plain);
+        out.push('\n');
+    } else {
+        for line in &lyrics.lines {
+            out.push_str(&format!("[{}]{}\n", format_timestamp(line.time), line.text));
+        }
+    }
+    out
+}
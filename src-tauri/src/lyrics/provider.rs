@@ -0,0 +1,30 @@
+use async_trait::async_trait;
+use std::path::PathBuf;
+
+use super::{local::LocalFiles, lrclib::Lrclib, ytmusic::YouTubeMusic, Lyrics, TrackQuery};
+use crate::settings::{LyricsProviderKind, LyricsSettings};
+
+/// A source of lyrics. `Ok(None)` means the provider has nothing for this track.
+#[async_trait]
+pub trait LyricsProvider: Send + Sync {
+    fn name(&self) -> &'static str;
+
+    async fn fetch(&self, app: &tauri::AppHandle, track: &TrackQuery) -> Result<Option<Lyrics>, String>;
+}
+
+/// Instantiate the enabled providers in the configured priority order
+pub fn build(cfg: &LyricsSettings) -> Vec<Box<dyn LyricsProvider>> {
+    cfg.providers
+        .iter()
+        .filter_map(|kind| -> Option<Box<dyn LyricsProvider>> {
+            match kind {
+                LyricsProviderKind::Local => {
+                    let folder = cfg.local_folder.as_deref().filter(|f| !f.is_empty())?;
+                    Some(Box::new(LocalFiles { folder: PathBuf::from(folder) }))
+                }
+                LyricsProviderKind::Lrclib => Some(Box::new(Lrclib { base_url: cfg.lrclib_url.clone() })),
+                LyricsProviderKind::YoutubeMusic => Some(Box::new(YouTubeMusic)),
+            }
+        })
+        .collect()
+}
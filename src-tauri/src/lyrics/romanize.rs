@@ -0,0 +1,292 @@
+use crate::settings::RomanizeSettings;
+
+/// Hepburn romaji for the base hiragana range, indexed from U+3041
+const HIRAGANA: [&str; 86] = [
+    "a", "a", "i", "i", "u", "u", "e", "e", "o", "o", // ぁあぃいぅうぇえぉお
+    "ka", "ga", "ki", "gi", "ku", "gu", "ke", "ge", "ko", "go", // かが…こご
+    "sa", "za", "shi", "ji", "su", "zu", "se", "ze", "so", "zo", // さざ…そぞ
+    "ta", "da", "chi", "ji", "tsu", "tsu", "zu", "te", "de", "to", "do", // ただちぢっつづてでとど
+    "na", "ni", "nu", "ne", "no", // な…の
+    "ha", "ba", "pa", "hi", "bi", "pi", "fu", "bu", "pu", "he", "be", "pe", "ho", "bo", "po", // は…ぽ
+    "ma", "mi", "mu", "me", "mo", // ま…も
+    "ya", "ya", "yu", "yu", "yo", "yo", // ゃやゅゆょよ
+    "ra", "ri", "ru", "re", "ro", // ら…ろ
+    "wa", "wa", "wi", "we", "wo", "n", "vu", // ゎわゐゑをんゔ
+    "ka", "ke", // ゕゖ
+];
+
+fn is_small_vowel(c: char) -> bool {
+    matches!(c, 'ぁ' | 'ぃ' | 'ぅ' | 'ぇ' | 'ぉ')
+}
+
+fn is_small_y(c: char) -> bool {
+    matches!(c, 'ゃ' | 'ゅ' | 'ょ')
+}
+
+/// Fold katakana onto hiragana so one table covers both
+fn to_hiragana(c: char) -> char {
+    match c {
+        '\u{30A1}'..='\u{30F6}' => char::from_u32(c as u32 - 0x60).unwrap_or(c),
+        _ => c,
+    }
+}
+
+fn is_katakana(c: char) -> bool {
+    matches!(c, '\u{30A1}'..='\u{30FA}')
+}
+
+fn is_kana(c: char) -> bool {
+    matches!(c, '\u{3041}'..='\u{3096}' | '\u{30A1}'..='\u{30FA}' | 'ー')
+}
+
+fn kana_romaji(c: char) -> Option<&'static str> {
+    let h = to_hiragana(c) as u32;
+    (0x3041..=0x3096).contains(&h).then(|| HIRAGANA[(h - 0x3041) as usize])
+}
+
+/// Kana to Hepburn romaji. Kanji and other characters pass through unchanged.
+fn romanize_kana(text: &str) -> String {
+    let chars: Vec<char> = text.chars().collect();
+    let mut out = String::new();
+    let mut geminate = false;
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        let h = to_hiragana(c);
+        i += 1;
+
+        if h == 'っ' {
+            geminate = true;
+            continue;
+        }
+        if c == 'ー' {
+            // Long vowel mark repeats the previous vowel
+            if let Some(v) = out.chars().last().filter(|v| "aiueo".contains(*v)) {
+                out.push(v);
+            }
+            continue;
+        }
+        let Some(mut syllable) = kana_romaji(c).map(str::to_string) else {
+            out.push(match c {
+                '、' => ',',
+                '。' => '.',
+                '　' => ' ',
+                '「' | '」' | '『' | '』' => '"',
+                _ => c,
+            });
+            geminate = false;
+            continue;
+        };
+
+        if let Some(&next) = chars.get(i) {
+            let next_h = to_hiragana(next);
+            if is_small_y(next_h) && syllable.ends_with('i') && syllable.len() > 1 {
+                // きゃ → kya, しゃ → sha, ちゃ → cha, じゃ → ja
+                let vowel = &kana_romaji(next).unwrap_or("ya")[1..];
+                syllable.pop();
+                if !matches!(syllable.as_str(), "sh" | "ch" | "j") {
+                    syllable.push('y');
+                }
+                syllable.push_str(vowel);
+                i += 1;
+            } else if is_small_vowel(next_h) && is_katakana(c) && (syllable.len() > 1 || h == 'う') {
+                // Extended katakana: ファ → fa, ティ → ti, ウィ → wi
+                let vowel = kana_romaji(next).unwrap_or("a");
+                syllable.pop();
+                if syllable.is_empty() {
+                    syllable.push('w');
+                }
+                syllable.push_str(vowel);
+                i += 1;
+            }
+        }
+
+        if h == 'ん' {
+            // Disambiguate ん before a vowel or y: kin'en vs kinen
+            let next_is_vowel = chars
+                .get(i)
+                .and_then(|n| kana_romaji(*n))
+                .is_some_and(|r| r.starts_with(['a', 'i', 'u', 'e', 'o', 'y']));
+            syllable = if next_is_vowel { "n'".to_string() } else { "n".to_string() };
+        }
+
+        if geminate {
+            if syllable.starts_with("ch") {
+                out.push('t');
+            } else if let Some(first) = syllable.chars().next().filter(|c| !"aiueon".contains(*c)) {
+                out.push(first);
+            }
+            geminate = false;
+        }
+        out.push_str(&syllable);
+    }
+    out
+}
+
+const HANGUL_INITIALS: [&str; 19] = [
+    "g", "kk", "n", "d", "tt", "r", "m", "b", "pp", "s", "ss", "", "j", "jj", "ch", "k", "t", "p", "h",
+];
+const HANGUL_VOWELS: [&str; 21] = [
+    "a", "ae", "ya", "yae", "eo", "e", "yeo", "ye", "o", "wa", "wae", "oe", "yo", "u", "wo", "we", "wi", "yu", "eu",
+    "ui", "i",
+];
+/// Final consonants at the end of a word or before another consonant
+const HANGUL_FINALS: [&str; 28] = [
+    "", "k", "k", "k", "n", "n", "n", "t", "l", "k", "m", "p", "l", "l", "p", "l", "m", "p", "p", "t", "t", "ng", "t",
+    "t", "k", "t", "p", "t",
+];
+/// Final consonants carried over onto a following syllable that starts with silent ㅇ
+const HANGUL_FINALS_LINKED: [&str; 28] = [
+    "", "g", "kk", "gs", "n", "nj", "n", "d", "r", "lg", "lm", "lb", "ls", "lt", "lp", "r", "m", "b", "bs", "s", "ss",
+    "ng", "j", "ch", "k", "t", "p", "",
+];
+
+fn hangul_parts(c: char) -> Option<(usize, usize, usize)> {
+    let s = (c as u32).checked_sub(0xAC00)?;
+    (s < 11172).then(|| ((s / 588) as usize, (s % 588 / 28) as usize, (s % 28) as usize))
+}
+
+/// Hangul syllables to Revised Romanization, with liaison across syllables
+fn romanize_hangul(text: &str) -> String {
+    let chars: Vec<char> = text.chars().collect();
+    let mut out = String::new();
+
+    for (i, &c) in chars.iter().enumerate() {
+        let Some((l, v, t)) = hangul_parts(c) else {
+            out.push(c);
+            continue;
+        };
+        let prev_final = i
+            .checked_sub(1)
+            .and_then(|p| hangul_parts(chars[p]))
+            .map_or(0, |(_, _, pt)| pt);
+        match l {
+            // A linked final already supplied this syllable's initial sound
+            11 if prev_final != 0 && prev_final != 21 => {}
+            // ㄹ followed by ㄹ is written "ll"
+            5 if prev_final == 8 => out.push('l'),
+            _ => out.push_str(HANGUL_INITIALS[l]),
+        }
+        out.push_str(HANGUL_VOWELS[v]);
+
+        let next_initial = chars.get(i + 1).and_then(|n| hangul_parts(*n)).map(|(nl, _, _)| nl);
+        if next_initial == Some(11) && t != 0 && t != 21 {
+            out.push_str(HANGUL_FINALS_LINKED[t]);
+        } else {
+            out.push_str(HANGUL_FINALS[t]);
+        }
+    }
+    out
+}
+
+fn cyrillic_latin(c: char) -> Option<&'static str> {
+    Some(match c {
+        'а' => "a", 'б' => "b", 'в' => "v", 'г' => "g", 'д' => "d", 'е' => "e", 'ё' => "yo",
+        'ж' => "zh", 'з' => "z", 'и' => "i", 'й' => "y", 'к' => "k", 'л' => "l", 'м' => "m",
+        'н' => "n", 'о' => "o", 'п' => "p", 'р' => "r", 'с' => "s", 'т' => "t", 'у' => "u",
+        'ф' => "f", 'х' => "kh", 'ц' => "ts", 'ч' => "ch", 'ш' => "sh", 'щ' => "shch", 'ъ' => "",
+        'ы' => "y", 'ь' => "", 'э' => "e", 'ю' => "yu", 'я' => "ya",
+        // Ukrainian and Belarusian
+        'є' => "ye", 'і' => "i", 'ї' => "yi", 'ґ' => "g", 'ў' => "w",
+        // Serbian and Macedonian
+        'ђ' => "dj", 'ј' => "j", 'љ' => "lj", 'њ' => "nj", 'ћ' => "c", 'џ' => "dz", 'ѓ' => "gj",
+        'ќ' => "kj", 'ѕ' => "dz",
+        _ => return None,
+    })
+}
+
+/// Cyrillic to Latin, preserving capitalization of the first letter
+fn romanize_cyrillic(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        let lower = c.to_lowercase().next().unwrap_or(c);
+        match cyrillic_latin(lower) {
+            Some(latin) if lower != c => {
+                let mut chars = latin.chars();
+                if let Some(first) = chars.next() {
+                    out.extend(first.to_uppercase());
+                    out.push_str(chars.as_str());
+                }
+            }
+            Some(latin) => out.push_str(latin),
+            None => out.push(c),
+        }
+    }
+    out
+}
+
+/// Transliterate the enabled scripts in a line. Returns `None` if nothing changed.
+pub fn romanize(text: &str, cfg: &RomanizeSettings) -> Option<String> {
+    let has = |pred: fn(char) -> bool| text.chars().any(pred);
+    let mut out = text.to_string();
+
+    if cfg.japanese && has(is_kana) {
+        out = romanize_kana(&out);
+    }
+    if cfg.korean && has(|c| hangul_parts(c).is_some()) {
+        out = romanize_hangul(&out);
+    }
+    if cfg.cyrillic && has(|c| cyrillic_latin(c.to_lowercase().next().unwrap_or(c)).is_some()) {
+        out = romanize_cyrillic(&out);
+    }
+
+    (out != text).then_some(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn check(f: fn(&str) -> String, cases: &[(&str, &str)]) {
+        for (input, expected) in cases {
+            assert_eq!(f(input), *expected, "{}", input);
+        }
+    }
+
+    #[test]
+    fn kana_yoon() {
+        check(
+            romanize_kana,
+            &[("きゃ", "kya"), ("しゃしん", "shashin"), ("ちょっと", "chotto"), ("じゅう", "juu"), ("キョウ", "kyou")],
+        );
+    }
+
+    #[test]
+    fn kana_sokuon() {
+        check(romanize_kana, &[("がっこう", "gakkou"), ("まっちゃ", "matcha"), ("ずっと", "zutto"), ("っ", "")]);
+    }
+
+    #[test]
+    fn kana_long_vowel_mark() {
+        check(romanize_kana, &[("ラーメン", "raamen"), ("コーヒー", "koohii"), ("ー", "")]);
+    }
+
+    #[test]
+    fn kana_n_before_vowel_or_y() {
+        check(romanize_kana, &[("きんえん", "kin'en"), ("こんや", "kon'ya"), ("しんぶん", "shinbun"), ("ほん", "hon")]);
+    }
+
+    #[test]
+    fn kana_extended_katakana_and_punctuation() {
+        check(romanize_kana, &[("ファン", "fan"), ("ティー", "tii"), ("ウィ", "wi"), ("はい、東京。", "hai,東京.")]);
+    }
+
+    #[test]
+    fn hangul_liaison() {
+        check(romanize_hangul, &[("한국어", "hangugeo"), ("음악", "eumak"), ("영어", "yeongeo"), ("서울", "seoul")]);
+    }
+
+    #[test]
+    fn hangul_double_rieul() {
+        check(romanize_hangul, &[("빨리", "ppalli"), ("실라", "silla")]);
+    }
+
+    #[test]
+    fn cyrillic_capitalization() {
+        check(
+            romanize_cyrillic,
+            &[("Щука", "Shchuka"), ("Жизнь", "Zhizn"), ("ещё Щи", "eshchyo Shchi"), ("Юля", "Yulya"), ("ЖЖ", "ZhZh")],
+        );
+    }
+}
@@ -0,0 +1,55 @@
+use async_trait::async_trait;
+use serde_json::json;
+
+use super::{provider::LyricsProvider, Lyrics, TrackQuery};
+use crate::innertube;
+
+/// YouTube Music's own "Lyrics" tab. Only plain text is available to the web client.
+pub struct YouTubeMusic;
+
+#[async_trait]
+impl LyricsProvider for YouTubeMusic {
+    fn name(&self) -> &'static str {
+        "youtube_music"
+    }
+
+    async fn fetch(&self, app: &tauri::AppHandle, track: &TrackQuery) -> Result<Option<Lyrics>, String> {
+        let Some(video_id) = track.video_id.as_deref().filter(|id| !id.is_empty()) else {
+            return Ok(None);
+        };
+
+        // The watch page lists a lyrics tab whose browse ID starts with MPLY
+        let next = innertube::call(app, "next", json!({ "videoId": video_id })).await?;
+        let tabs = next
+            .pointer("/contents/singleColumnMusicWatchNextResultsRenderer/tabbedRenderer/watchNextTabbedResultsRenderer/tabs")
+            .and_then(|t| t.as_array())
+            .cloned()
+            .unwrap_or_default();
+        let browse_id = tabs.iter().find_map(|tab| {
+            tab.pointer("/tabRenderer/endpoint/browseEndpoint/browseId")?
+                .as_str()
+                .filter(|id| id.starts_with("MPLY"))
+                .map(str::to_string)
+        });
+        let Some(browse_id) = browse_id else {
+            return Ok(None);
+        };
+
+        let page = innertube::call(app, "browse", json!({ "browseId": browse_id })).await?;
+        let plain = page
+            .pointer("/contents/sectionListRenderer/contents/0/musicDescriptionShelfRenderer/description")
+            .map(innertube::text)
+            .unwrap_or_default();
+        if plain.trim().is_empty() {
+            return Ok(None);
+        }
+
+        Ok(Some(Lyrics {
+            lines: Vec::new(),
+            plain,
+            instrumental: false,
+            confidence: 1.0,
+            source: self.name().to_string(),
+        }))
+    }
+}
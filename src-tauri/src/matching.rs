@@ -0,0 +1,178 @@
+/// Bracketed or trailing title decorations that don't identify a different recording
+const NOISE_WORDS: &[&str] = &[
+    "official",
+    "video",
+    "audio",
+    "lyric",
+    "lyrics",
+    "visualizer",
+    "remaster",
+    "remastered",
+    "hd",
+    "hq",
+    "4k",
+    "mv",
+    "m/v",
+    "explicit",
+    "clean",
+];
+
+/// Lowercase, drop punctuation and collapse whitespace
+pub fn normalize(s: &str) -> String {
+    s.to_lowercase()
+        .chars()
+        .map(|c| if c.is_alphanumeric() { c } else { ' ' })
+        .collect::<String>()
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn is_noise(segment: &str) -> bool {
+    let lower = segment.to_lowercase();
+    lower.starts_with("feat") || lower.starts_with("ft.") || lower.starts_with("with ")
+        || lower.split(|c: char| !c.is_alphanumeric() && c != '/')
+            .any(|w| NOISE_WORDS.contains(&w))
+}
+
+/// Strip decorations like "(Official Video)", "[Remastered 2011]", "- 2009 Remaster"
+/// and "feat. X" from a track title
+pub fn clean_title(title: &str) -> String {
+    let mut out = String::with_capacity(title.len());
+    let mut rest = title;
+
+    // Remove bracketed segments that only carry noise
+    while let Some(open) = rest.find(['(', '[']) {
+        let close_char = if rest[open..].starts_with('(') { ')' } else { ']' };
+        let Some(close) = rest[open..].find(close_char).map(|i| open + i) else { break };
+        out.push_str(&rest[..open]);
+        let inner = &rest[open + 1..close];
+        if !is_noise(inner) {
+            out.push_str(&rest[open..=close]);
+        }
+        rest = &rest[close + 1..];
+    }
+    out.push_str(rest);
+
+    // Trailing " - 2011 Remaster" style suffixes
+    if let Some(idx) = out.rfind(" - ") {
+        if is_noise(&out[idx + 3..]) {
+            out.truncate(idx);
+        }
+    }
+
+    // Unbracketed "feat. X" / "ft. X". ASCII lowercasing keeps byte offsets valid for `out`.
+    let lower = out.to_ascii_lowercase();
+    for marker in [" feat. ", " feat ", " ft. ", " ft "] {
+        if let Some(idx) = lower.find(marker) {
+            out.truncate(idx);
+            break;
+        }
+    }
+
+    out.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Similarity of two strings in `0.0..=1.0` after normalization.
+/// Equal strings score 1, containment 0.8, otherwise token overlap (Jaccard).
+pub fn similarity(a: &str, b: &str) -> f64 {
+    let (a, b) = (normalize(a), normalize(b));
+    if a.is_empty() || b.is_empty() {
+        return 0.0;
+    }
+    if a == b {
+        return 1.0;
+    }
+    if a.contains(&b) || b.contains(&a) {
+        return 0.8;
+    }
+    let ta: std::collections::HashSet<&str> = a.split(' ').collect();
+    let tb: std::collections::HashSet<&str> = b.split(' ').collect();
+    let common = ta.intersection(&tb).count() as f64;
+    let total = ta.union(&tb).count() as f64;
+    common / total * 0.8
+}
+
+/// Closeness of two durations in seconds, `0.0..=1.0`
+pub fn duration_score(expected: f64, actual: f64) -> f64 {
+    let delta = (expected - actual).abs();
+    if delta <= 2.0 {
+        1.0
+    } else if delta <= 5.0 {
+        0.7
+    } else if delta <= 10.0 {
+        0.4
+    } else {
+        0.0
+    }
+}
+
+/// What's known about one side of a match. Empty strings and zero durations are unknown.
+pub struct TrackFields<'a> {
+    pub title: &'a str,
+    pub artist: &'a str,
+    pub album: &'a str,
+    /// Seconds
+    pub duration: f64,
+}
+
+/// Weighted match confidence in `0.0..=1.0`. Album and duration only count when both sides know them.
+pub fn confidence(expected: &TrackFields, actual: &TrackFields) -> f64 {
+    let mut total = 0.45 * similarity(&clean_title(expected.title), &clean_title(actual.title))
+        + 0.3 * similarity(expected.artist, actual.artist);
+    let mut weight = 0.75;
+
+    if !expected.album.is_empty() && !actual.album.is_empty() {
+        total += 0.1 * similarity(expected.album, actual.album);
+        weight += 0.1;
+    }
+    if expected.duration > 0.0 && actual.duration > 0.0 {
+        total += 0.25 * duration_score(expected.duration, actual.duration);
+        weight += 0.25;
+    }
+    total / weight
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn clean_title_strips_feat_after_non_ascii() {
+        // 'İ' is two bytes but lowercases to three, which used to shift the cut point
+        assert_eq!(clean_title("İstanbul Feat. Someone"), "İstanbul");
+        assert_eq!(clean_title("ÅÄÖ ft. X"), "ÅÄÖ");
+    }
+
+    #[test]
+    fn clean_title_strips_noise() {
+        for (input, expected) in [
+            ("Song (Official Video)", "Song"),
+            ("Song [Official Music Video]", "Song"),
+            ("Song (feat. Someone)", "Song"),
+            ("Song feat. Someone", "Song"),
+            ("Song ft. Someone", "Song"),
+            ("Song - Remastered 2011", "Song"),
+            ("Song - 2009 Remaster", "Song"),
+            ("Song (Live) [Lyric Video]", "Song (Live)"),
+            ("Song - Acoustic", "Song - Acoustic"),
+        ] {
+            assert_eq!(clean_title(input), expected, "{}", input);
+        }
+    }
+
+    #[test]
+    fn similarity_is_bounded_and_symmetric() {
+        let samples = ["Hello World", "hello, world!", "Hello", "World Hello Again", "Goodbye", "", "  "];
+        for a in samples {
+            for b in samples {
+                let s = similarity(a, b);
+                assert!((0.0..=1.0).contains(&s), "{:?} vs {:?} = {}", a, b, s);
+                assert_eq!(s, similarity(b, a), "{:?} vs {:?}", a, b);
+            }
+        }
+        assert_eq!(similarity("Hello World", "hello, world!"), 1.0);
+        assert_eq!(similarity("Hello", "Hello World"), 0.8);
+        assert_eq!(similarity("", ""), 0.0);
+    }
+}
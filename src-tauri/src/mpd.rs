@@ -0,0 +1,647 @@
+use async_trait::async_trait;
+use serde_json::Value;
+use std::fmt::Write as _;
+use tauri::{Emitter, Manager};
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::broadcast::error::{RecvError, TryRecvError};
+use tokio::sync::{broadcast, watch};
+
+use crate::control::{self, parse_time, Request};
+use crate::listener::Listener;
+use crate::now_playing::{NowPlaying, NowPlayingState, Queue, QueueState, RepeatMode, Track};
+use crate::settings::MpdSettings;
+
+const GREETING: &str = "OK MPD 0.23.5\n";
+
+/// MPD error codes used in `ACK` replies
+const ACK_ERROR_ARG: u32 = 2;
+const ACK_ERROR_UNKNOWN: u32 = 5;
+const ACK_ERROR_NO_EXIST: u32 = 50;
+const ACK_ERROR_SYSTEM: u32 = 52;
+
+const SUPPORTED: [&str; 35] = [
+    "add", "addid", "clearerror", "close", "command_list_begin", "command_list_end", "command_list_ok_begin",
+    "commands", "currentsong", "decoders", "find", "idle", "listplaylists", "next", "noidle", "notcommands",
+    "outputs", "pause", "ping", "play", "playid", "playlistid", "playlistinfo", "plchanges", "previous",
+    "random", "repeat", "search", "seek", "seekcur", "setvol", "stats", "status", "stop", "tagtypes",
+];
+
+/// Subsystems we report to `idle`
+const SUBSYSTEMS: [&str; 4] = ["player", "mixer", "options", "playlist"];
+
+pub struct MpdState {
+    /// Changed subsystems, for clients in `idle`
+    changes: broadcast::Sender<&'static str>,
+    server: Listener,
+}
+
+impl Default for MpdState {
+    fn default() -> Self {
+        Self { changes: broadcast::channel(64).0, server: Listener::default() }
+    }
+}
+
+fn notify(app: &tauri::AppHandle, subsystem: &'static str) {
+    let Some(state) = app.try_state::<MpdState>() else { return };
+    let _ = state.changes.send(subsystem);
+}
+
+/// Wake idling clients for what changed. The position moves on every poll and isn't an event.
+pub fn now_playing_changed(app: &tauri::AppHandle, prev: &NowPlaying, next: &NowPlaying) {
+    if prev.track != next.track || prev.is_playing != next.is_playing {
+        notify(app, "player");
+    }
+    if prev.volume != next.volume {
+        notify(app, "mixer");
+    }
+    if prev.shuffle != next.shuffle || prev.repeat != next.repeat {
+        notify(app, "options");
+    }
+}
+
+pub fn queue_changed(app: &tauri::AppHandle) {
+    notify(app, "playlist");
+}
+
+/// (Re)start the listener with `cfg`, or stop it if disabled
+pub async fn restart(app: &tauri::AppHandle, cfg: &MpdSettings) -> Result<(), String> {
+    let addr = cfg.enabled.then_some((cfg.bind_address.as_str(), cfg.port));
+    let handle = app.clone();
+    app.state::<MpdState>()
+        .server
+        .restart("MPD server", addr, |listener, shutdown| serve(handle, listener, shutdown))
+        .await
+}
+
+/// What a connection needs from the app, so the protocol can be exercised without one
+#[async_trait]
+trait Player: Clone + Send + Sync + 'static {
+    /// Run one command, appending its reply lines to `out`
+    async fn run(&self, name: &str, args: &[String], out: &mut String) -> Result<(), Ack>;
+
+    fn changes(&self) -> broadcast::Receiver<&'static str>;
+}
+
+#[async_trait]
+impl Player for tauri::AppHandle {
+    async fn run(&self, name: &str, args: &[String], out: &mut String) -> Result<(), Ack> {
+        run(self, name, args, out).await
+    }
+
+    fn changes(&self) -> broadcast::Receiver<&'static str> {
+        self.state::<MpdState>().changes.subscribe()
+    }
+}
+
+async fn serve<P: Player>(player: P, listener: TcpListener, mut shutdown: watch::Receiver<()>) {
+    loop {
+        tokio::select! {
+            accepted = listener.accept() => {
+                let Ok((stream, _)) = accepted else { continue };
+                let (player, shutdown) = (player.clone(), shutdown.clone());
+                tauri::async_runtime::spawn(async move {
+                    if let Err(e) = handle(player, stream, shutdown).await {
+                        eprintln!("MPD connection failed: {}", e);
+                    }
+                });
+            }
+            _ = shutdown.changed() => return,
+        }
+    }
+}
+
+/// Split a command line into words, honouring double quotes and backslash escapes
+fn tokenize(line: &str) -> Result<Vec<String>, String> {
+    let mut words = Vec::new();
+    let mut chars = line.trim().chars().peekable();
+
+    while let Some(&c) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+            continue;
+        }
+        let mut word = String::new();
+        if c == '"' {
+            chars.next();
+            loop {
+                match chars.next() {
+                    Some('"') => break,
+                    Some('\\') => word.extend(chars.next()),
+                    Some(c) => word.push(c),
+                    None => return Err("Missing closing '\"'".to_string()),
+                }
+            }
+        } else {
+            while let Some(&c) = chars.peek() {
+                if c.is_whitespace() {
+                    break;
+                }
+                word.push(c);
+                chars.next();
+            }
+        }
+        words.push(word);
+    }
+    Ok(words)
+}
+
+/// Failure of a single command, rendered as an `ACK` line
+struct Ack {
+    code: u32,
+    message: String,
+}
+
+impl Ack {
+    fn new(code: u32, message: impl Into<String>) -> Self {
+        Self { code, message: message.into() }
+    }
+}
+
+fn song_url(video_id: &str) -> String {
+    format!("https://music.youtube.com/watch?v={}", video_id)
+}
+
+/// Tag values must stay on one line
+fn clean(s: &str) -> String {
+    s.replace(['\n', '\r'], " ")
+}
+
+fn write_song(out: &mut String, track: &Track, pos: Option<usize>) {
+    let _ = writeln!(out, "file: {}", song_url(&track.video_id));
+    let _ = writeln!(out, "Title: {}", clean(&track.title));
+    let _ = writeln!(out, "Artist: {}", clean(&track.artist));
+    if !track.album.is_empty() {
+        let _ = writeln!(out, "Album: {}", clean(&track.album));
+    }
+    if track.duration > 0.0 {
+        let _ = writeln!(out, "Time: {}", track.duration.round() as u64);
+        let _ = writeln!(out, "duration: {:.3}", track.duration);
+    }
+    // Queue positions double as song IDs
+    if let Some(pos) = pos {
+        let _ = writeln!(out, "Pos: {}\nId: {}", pos, pos);
+    }
+}
+
+fn snapshot(app: &tauri::AppHandle) -> (NowPlaying, Queue) {
+    let np = app.state::<NowPlayingState>().0.lock().unwrap().clone();
+    let queue = app.state::<QueueState>().0.lock().unwrap().clone();
+    (np, queue)
+}
+
+fn write_status(out: &mut String, np: &NowPlaying, queue: &Queue) {
+    let state = match (&np.track, np.is_playing) {
+        (None, _) => "stop",
+        (Some(_), true) => "play",
+        (Some(_), false) => "pause",
+    };
+    let _ = writeln!(out, "volume: {}", np.volume.round() as i64);
+    let _ = writeln!(out, "repeat: {}", u8::from(np.repeat != RepeatMode::Off));
+    let _ = writeln!(out, "random: {}", u8::from(np.shuffle));
+    let _ = writeln!(out, "single: {}", u8::from(np.repeat == RepeatMode::One));
+    let _ = writeln!(out, "consume: 0");
+    let _ = writeln!(out, "playlist: {}", queue.version);
+    let _ = writeln!(out, "playlistlength: {}", queue.tracks.len());
+    let _ = writeln!(out, "state: {}", state);
+    if let (Some(track), Some(pos)) = (&np.track, queue.index) {
+        let _ = writeln!(out, "song: {}\nsongid: {}", pos, pos);
+        let _ = writeln!(out, "time: {}:{}", np.current_time as u64, track.duration as u64);
+        let _ = writeln!(out, "elapsed: {:.3}", np.current_time);
+        let _ = writeln!(out, "duration: {:.3}", track.duration);
+        if pos + 1 < queue.tracks.len() {
+            let _ = writeln!(out, "nextsong: {}\nnextsongid: {}", pos + 1, pos + 1);
+        }
+    }
+}
+
+/// Forward to the shared control commands, turning failures into ACKs
+async fn forward(app: &tauri::AppHandle, command: &str, args: Vec<String>) -> Result<Value, Ack> {
+    let response = control::execute(app, &Request { command: command.to_string(), args }).await;
+    if response.ok {
+        Ok(response.data)
+    } else {
+        Err(Ack::new(ACK_ERROR_SYSTEM, response.error.unwrap_or_default()))
+    }
+}
+
+fn arg<'a>(args: &'a [String], i: usize) -> Result<&'a str, Ack> {
+    args.get(i).map(String::as_str).ok_or_else(|| Ack::new(ACK_ERROR_ARG, "too few arguments"))
+}
+
+fn parse_pos(s: &str, queue: &Queue) -> Result<usize, Ack> {
+    let pos: usize = s.parse().map_err(|_| Ack::new(ACK_ERROR_ARG, format!("Integer expected: {}", s)))?;
+    if pos < queue.tracks.len() {
+        Ok(pos)
+    } else {
+        Err(Ack::new(ACK_ERROR_NO_EXIST, "No such song"))
+    }
+}
+
+fn emit(app: &tauri::AppHandle, event: &str, payload: impl serde::Serialize + Clone) -> Result<(), Ack> {
+    app.emit(event, payload).map_err(|e| Ack::new(ACK_ERROR_SYSTEM, e.to_string()))
+}
+
+/// Toggle shuffle or repeat through the player if it doesn't already match the request
+fn set_flag(app: &tauri::AppHandle, event: &str, current: bool, wanted: &str) -> Result<(), Ack> {
+    let wanted = match wanted {
+        "0" => false,
+        "1" => true,
+        _ => return Err(Ack::new(ACK_ERROR_ARG, "Boolean (0/1) expected")),
+    };
+    if current != wanted {
+        emit(app, event, ())?;
+    }
+    Ok(())
+}
+
+/// Run one command, appending its reply lines to `out`
+async fn run(app: &tauri::AppHandle, name: &str, args: &[String], out: &mut String) -> Result<(), Ack> {
+    let (np, queue) = snapshot(app);
+
+    match name {
+        "ping" | "clearerror" => {}
+        "status" => write_status(out, &np, &queue),
+        "currentsong" => {
+            if let Some(track) = &np.track {
+                write_song(out, track, queue.index);
+            }
+        }
+        "stats" => {
+            let total: f64 = queue.tracks.iter().map(|t| t.duration).sum();
+            let _ = writeln!(out, "songs: {}\nplaytime: {}", queue.tracks.len(), total as u64);
+        }
+        "playlistinfo" | "playlistid" | "plchanges" => {
+            // plchanges reports everything, clients cope with a full refresh
+            let only = match (name, args.first()) {
+                ("playlistinfo" | "playlistid", Some(pos)) => Some(parse_pos(pos, &queue)?),
+                _ => None,
+            };
+            for (pos, track) in queue.tracks.iter().enumerate() {
+                if only.is_none_or(|o| o == pos) {
+                    write_song(out, track, Some(pos));
+                }
+            }
+        }
+        "play" | "playid" => match args.first() {
+            Some(pos) => emit(app, "media-play-index", parse_pos(pos, &queue)?)?,
+            None => {
+                forward(app, "play", Vec::new()).await?;
+            }
+        },
+        "pause" => {
+            let command = match args.first().map(String::as_str) {
+                Some("0") => "play",
+                Some("1") => "pause",
+                _ => "play-pause",
+            };
+            forward(app, command, Vec::new()).await?;
+        }
+        "stop" => {
+            if np.is_playing {
+                forward(app, "pause", Vec::new()).await?;
+            }
+        }
+        "next" => {
+            forward(app, "next", Vec::new()).await?;
+        }
+        "previous" => {
+            forward(app, "prev", Vec::new()).await?;
+        }
+        "seekcur" | "seek" => {
+            // seek takes a song position first; only the current song can be seeked
+            let time = if name == "seek" { arg(args, 1)? } else { arg(args, 0)? };
+            let secs = match time.strip_prefix(['+', '-']) {
+                Some(rel) => {
+                    let delta = parse_time(rel).ok_or_else(|| Ack::new(ACK_ERROR_ARG, "Invalid time"))?;
+                    if time.starts_with('-') { np.current_time - delta } else { np.current_time + delta }
+                }
+                None => parse_time(time).ok_or_else(|| Ack::new(ACK_ERROR_ARG, "Invalid time"))?,
+            };
+            forward(app, "seek", vec![secs.max(0.0).to_string()]).await?;
+        }
+        "setvol" => {
+            forward(app, "volume", vec![arg(args, 0)?.to_string()]).await?;
+        }
+        "random" => set_flag(app, "media-shuffle", np.shuffle, arg(args, 0)?)?,
+        "repeat" => set_flag(app, "media-repeat", np.repeat != RepeatMode::Off, arg(args, 0)?)?,
+        "add" | "addid" => {
+            forward(app, "enqueue", vec![arg(args, 0)?.to_string()]).await?;
+            if name == "addid" {
+                let _ = writeln!(out, "Id: {}", queue.tracks.len());
+            }
+        }
+        "search" | "find" => {
+            // Arguments are TYPE VALUE pairs; the tag type is ignored and values form one query
+            let query: Vec<String> = args.iter().skip(1).step_by(2).cloned().collect();
+            let data = forward(app, "search", query).await?;
+            let tracks: Vec<Track> = serde_json::from_value(data).unwrap_or_default();
+            for track in &tracks {
+                write_song(out, track, None);
+            }
+        }
+        "outputs" => out.push_str("outputid: 0\noutputname: GoyMusic\noutputenabled: 1\n"),
+        "tagtypes" => out.push_str("tagtype: Artist\ntagtype: Album\ntagtype: Title\n"),
+        "commands" => SUPPORTED.iter().for_each(|c| { let _ = writeln!(out, "command: {}", c); }),
+        "notcommands" | "listplaylists" | "decoders" => {}
+        _ => return Err(Ack::new(ACK_ERROR_UNKNOWN, format!("unknown command \"{}\"", name))),
+    }
+    Ok(())
+}
+
+fn mark(pending: &mut Vec<&'static str>, subsystems: &[&'static str]) {
+    for subsystem in subsystems {
+        if !pending.contains(subsystem) {
+            pending.push(subsystem);
+        }
+    }
+}
+
+/// Collect changes received since the last look. A receiver that lagged missed some, so
+/// everything counts as changed.
+fn drain(changes: &mut broadcast::Receiver<&'static str>, pending: &mut Vec<&'static str>) {
+    loop {
+        match changes.try_recv() {
+            Ok(subsystem) => mark(pending, &[subsystem]),
+            Err(TryRecvError::Lagged(_)) => mark(pending, &SUBSYSTEMS),
+            Err(_) => return,
+        }
+    }
+}
+
+async fn handle<P: Player>(player: P, stream: TcpStream, mut shutdown: watch::Receiver<()>) -> std::io::Result<()> {
+    let (reader, mut writer) = stream.into_split();
+    let mut lines = BufReader::new(reader).lines();
+    // Subscribed for the whole connection: changes between two idles are reported by the next one
+    let mut changes = player.changes();
+    let mut pending: Vec<&'static str> = Vec::new();
+    writer.write_all(GREETING.as_bytes()).await?;
+
+    // Commands collected between command_list_begin and command_list_end, plus whether to print list_OK
+    let mut list: Option<(Vec<String>, bool)> = None;
+
+    loop {
+        let line = tokio::select! {
+            line = lines.next_line() => match line? {
+                Some(line) => line,
+                None => return Ok(()),
+            },
+            _ = shutdown.changed() => return Ok(()),
+        };
+
+        match line.trim() {
+            "command_list_begin" => { list = Some((Vec::new(), false)); continue; }
+            "command_list_ok_begin" => { list = Some((Vec::new(), true)); continue; }
+            "close" => return Ok(()),
+            _ => {}
+        }
+
+        let (commands, list_ok) = match list.take() {
+            Some((commands, ok)) if line.trim() == "command_list_end" => (commands, ok),
+            Some((mut commands, ok)) => {
+                commands.push(line);
+                list = Some((commands, ok));
+                continue;
+            }
+            None => (vec![line], false),
+        };
+
+        let mut out = String::new();
+        let mut failed = false;
+        for (i, command) in commands.iter().enumerate() {
+            let words = match tokenize(command) {
+                Ok(words) if !words.is_empty() => words,
+                Ok(_) => { failed = true; let _ = writeln!(out, "ACK [{}@{}] {{}} No command given", ACK_ERROR_UNKNOWN, i); break; }
+                Err(e) => { failed = true; let _ = writeln!(out, "ACK [{}@{}] {{}} {}", ACK_ERROR_ARG, i, e); break; }
+            };
+            let (name, args) = (words[0].as_str(), &words[1..]);
+
+            if name == "idle" {
+                // Block until a requested subsystem changes or the client sends noidle
+                writer.write_all(out.as_bytes()).await?;
+                out.clear();
+                let wanted: Vec<&str> =
+                    if args.is_empty() { SUBSYSTEMS.to_vec() } else { args.iter().map(String::as_str).collect() };
+                loop {
+                    drain(&mut changes, &mut pending);
+                    let ready: Vec<&'static str> = pending.iter().copied().filter(|s| wanted.contains(s)).collect();
+                    if !ready.is_empty() {
+                        pending.retain(|s| !ready.contains(s));
+                        for subsystem in ready {
+                            let _ = writeln!(out, "changed: {}", subsystem);
+                        }
+                        break;
+                    }
+                    tokio::select! {
+                        changed = changes.recv() => match changed {
+                            Ok(subsystem) => mark(&mut pending, &[subsystem]),
+                            Err(RecvError::Lagged(_)) => mark(&mut pending, &SUBSYSTEMS),
+                            Err(RecvError::Closed) => return Ok(()),
+                        },
+                        line = lines.next_line() => {
+                            let Some(line) = line? else { return Ok(()) };
+                            // Only noidle may interrupt idle; anything else fails it rather than vanishing
+                            if line.trim() != "noidle" {
+                                failed = true;
+                                let _ = writeln!(
+                                    out,
+                                    "ACK [{}@{}] {{idle}} Only \"noidle\" is allowed during idle, got \"{}\"",
+                                    ACK_ERROR_UNKNOWN,
+                                    i,
+                                    line.trim()
+                                );
+                            }
+                            break;
+                        }
+                        _ = shutdown.changed() => return Ok(()),
+                    }
+                }
+                if failed {
+                    break;
+                }
+                continue;
+            }
+            if name == "noidle" {
+                continue;
+            }
+
+            if let Err(ack) = player.run(name, args, &mut out).await {
+                failed = true;
+                let _ = writeln!(out, "ACK [{}@{}] {{{}}} {}", ack.code, i, name, ack.message);
+                break;
+            }
+            if list_ok {
+                out.push_str("list_OK\n");
+            }
+        }
+        if !failed {
+            out.push_str("OK\n");
+        }
+        writer.write_all(out.as_bytes()).await?;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::{Lines, WriteHalf};
+
+    /// Answers `ping`, echoes `echo` arguments and fails everything else like an unknown command
+    #[derive(Clone)]
+    struct Stub {
+        changes: broadcast::Sender<&'static str>,
+    }
+
+    #[async_trait]
+    impl Player for Stub {
+        async fn run(&self, name: &str, args: &[String], out: &mut String) -> Result<(), Ack> {
+            match name {
+                "ping" => Ok(()),
+                "echo" => {
+                    args.iter().for_each(|a| {
+                        let _ = writeln!(out, "arg: {}", a);
+                    });
+                    Ok(())
+                }
+                _ => Err(Ack::new(ACK_ERROR_UNKNOWN, format!("unknown command \"{}\"", name))),
+            }
+        }
+
+        fn changes(&self) -> broadcast::Receiver<&'static str> {
+            self.changes.subscribe()
+        }
+    }
+
+    struct Client {
+        lines: Lines<BufReader<tokio::io::ReadHalf<TcpStream>>>,
+        writer: WriteHalf<TcpStream>,
+        _shutdown: watch::Sender<()>,
+    }
+
+    impl Client {
+        async fn send(&mut self, text: &str) {
+            self.writer.write_all(text.as_bytes()).await.unwrap();
+        }
+
+        /// Lines up to and including the closing `OK` or `ACK`
+        async fn reply(&mut self) -> Vec<String> {
+            let mut reply = Vec::new();
+            loop {
+                let line = self.lines.next_line().await.unwrap().expect("connection closed");
+                let done = line == "OK" || line.starts_with("ACK ");
+                reply.push(line);
+                if done {
+                    return reply;
+                }
+            }
+        }
+    }
+
+    /// Serve `stub` on a loopback port and connect to it, past the greeting
+    async fn connect(stub: Stub) -> Client {
+        let listener = TcpListener::bind(("127.0.0.1", 0)).await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let (tx, rx) = watch::channel(());
+        tokio::spawn(serve(stub, listener, rx));
+
+        let (reader, writer) = tokio::io::split(TcpStream::connect(addr).await.unwrap());
+        let mut client = Client { lines: BufReader::new(reader).lines(), writer, _shutdown: tx };
+        assert_eq!(client.lines.next_line().await.unwrap().unwrap(), GREETING.trim_end());
+        client
+    }
+
+    fn stub(capacity: usize) -> Stub {
+        Stub { changes: broadcast::channel(capacity).0 }
+    }
+
+    #[test]
+    fn tokenize_handles_quotes_and_escapes() {
+        assert_eq!(
+            tokenize(r#"  find artist "Sigur Rós" title "say \"hi\" \\ bye" "#).unwrap(),
+            ["find", "artist", "Sigur Rós", "title", r#"say "hi" \ bye"#]
+        );
+        assert_eq!(tokenize("   ").unwrap(), Vec::<String>::new());
+        assert!(tokenize(r#"find "open"#).is_err());
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn replies_to_single_commands() {
+        let mut client = connect(stub(8)).await;
+        client.send("ping\n").await;
+        assert_eq!(client.reply().await, ["OK"]);
+        client.send("echo \"two words\" plain \"qu\\\"ote\"\n").await;
+        assert_eq!(client.reply().await, ["arg: two words", "arg: plain", "arg: qu\"ote", "OK"]);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn acks_name_the_failing_command_and_its_list_index() {
+        let mut client = connect(stub(8)).await;
+        client.send("bogus 1\n").await;
+        assert_eq!(client.reply().await, ["ACK [5@0] {bogus} unknown command \"bogus\""]);
+        client.send("echo \"open\n").await;
+        assert_eq!(client.reply().await, ["ACK [2@0] {} Missing closing '\"'"]);
+        client.send("\n").await;
+        assert_eq!(client.reply().await, ["ACK [5@0] {} No command given"]);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn runs_command_lists() {
+        let mut client = connect(stub(8)).await;
+        client.send("command_list_ok_begin\nping\necho a\ncommand_list_end\n").await;
+        assert_eq!(client.reply().await, ["list_OK", "arg: a", "list_OK", "OK"]);
+
+        // Execution stops at the first failure, which is reported with its position
+        client.send("command_list_begin\necho a\nbogus\necho b\ncommand_list_end\n").await;
+        assert_eq!(client.reply().await, ["arg: a", "ACK [5@1] {bogus} unknown command \"bogus\""]);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn idle_reports_only_requested_subsystems() {
+        let stub = stub(8);
+        let mut client = connect(stub.clone()).await;
+        stub.changes.send("player").unwrap();
+        client.send("idle mixer\n").await;
+        stub.changes.send("mixer").unwrap();
+        assert_eq!(client.reply().await, ["changed: mixer", "OK"]);
+
+        // The player change is still pending for the next idle
+        client.send("idle\n").await;
+        assert_eq!(client.reply().await, ["changed: player", "OK"]);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn idle_treats_lag_as_everything_changed() {
+        let stub = stub(2);
+        let mut client = connect(stub.clone()).await;
+        for _ in 0..5 {
+            stub.changes.send("player").unwrap();
+        }
+        client.send("idle options\n").await;
+        assert_eq!(client.reply().await, ["changed: options", "OK"]);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn noidle_ends_idle() {
+        let mut client = connect(stub(8)).await;
+        client.send("idle\n").await;
+        client.send("noidle\n").await;
+        assert_eq!(client.reply().await, ["OK"]);
+        client.send("ping\n").await;
+        assert_eq!(client.reply().await, ["OK"]);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn other_commands_during_idle_are_refused() {
+        let mut client = connect(stub(8)).await;
+        client.send("idle
+").await;
+        client.send("ping
+").await;
+        assert_eq!(client.reply().await, ["ACK [5@0] {idle} Only \"noidle\" is allowed during idle, got \"ping\""]);
+
+        // The connection carries on normally afterwards
+        client.send("ping
+").await;
+        assert_eq!(client.reply().await, ["OK"]);
+    }
+}
@@ -0,0 +1,122 @@
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, Instant, SystemTime};
+use tauri::Manager;
+use tauri_plugin_notification::NotificationExt;
+
+use crate::now_playing::{LikeStatus, NowPlayingState, Track};
+use crate::settings::SettingsState;
+
+/// When the last track notification was shown
+#[derive(Default)]
+pub struct NotificationState(pub Mutex<Option<Instant>>);
+
+/// Covers kept in the cache dir; the least recently written go first
+const MAX_COVERS: usize = 200;
+
+/// Delete the oldest covers beyond [`MAX_COVERS`]
+fn prune_covers(dir: &Path) {
+    let Ok(entries) = std::fs::read_dir(dir) else { return };
+    let mut covers: Vec<(SystemTime, PathBuf)> = entries
+        .flatten()
+        .filter_map(|e| Some((e.metadata().ok()?.modified().ok()?, e.path())))
+        .collect();
+    if covers.len() <= MAX_COVERS {
+        return;
+    }
+    covers.sort();
+    for (_, path) in &covers[..covers.len() - MAX_COVERS] {
+        let _ = std::fs::remove_file(path);
+    }
+}
+
+/// Download a track's cover into the cache dir, reusing an earlier download if present
+async fn cached_cover(app: &tauri::AppHandle, track: &Track) -> Result<PathBuf, String> {
+    // The id ends up in a file name, so keep only what real video ids contain
+    let id: String =
+        track.video_id.chars().filter(|c| c.is_ascii_alphanumeric() || *c == '-' || *c == '_').collect();
+    if id.is_empty() {
+        return Err(format!("Invalid video id \"{}\"", track.video_id));
+    }
+    let dir = app.path().app_cache_dir().map_err(|e| e.to_string())?.join("covers");
+    let path = dir.join(format!("{}.jpg", id));
+    if path.exists() {
+        return Ok(path);
+    }
+
+    let bytes = reqwest::get(&track.thumb_url)
+        .await
+        .map_err(|e| e.to_string())?
+        .error_for_status()
+        .map_err(|e| e.to_string())?
+        .bytes()
+        .await
+        .map_err(|e| e.to_string())?;
+    std::fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+    std::fs::write(&path, &bytes).map_err(|e| e.to_string())?;
+    prune_covers(&dir);
+    Ok(path)
+}
+
+fn main_window_focused(app: &tauri::AppHandle) -> bool {
+    app.get_webview_window("main")
+        .and_then(|w| w.is_focused().ok())
+        .unwrap_or(false)
+}
+
+/// Announce a newly started track, unless disabled, too soon after the last one,
+/// or the user is already looking at the player
+pub fn track_changed(app: &tauri::AppHandle, track: &Track) {
+    let cfg = app.state::<SettingsState>().0.lock().unwrap().notifications.clone();
+    if !cfg.enabled || track.video_id.is_empty() || main_window_focused(app) {
+        return;
+    }
+
+    {
+        let state = app.state::<NotificationState>();
+        let mut last = state.0.lock().unwrap();
+        if last.is_some_and(|t| t.elapsed() < Duration::from_secs(cfg.min_interval_secs)) {
+            return;
+        }
+        *last = Some(Instant::now());
+    }
+
+    let app = app.clone();
+    let track = track.clone();
+    tauri::async_runtime::spawn(async move {
+        let cover = if cfg.show_cover && !track.thumb_url.is_empty() {
+            cached_cover(&app, &track).await
+                .map_err(|e| eprintln!("Failed to cache cover for {}: {}", track.video_id, e))
+                .ok()
+        } else {
+            None
+        };
+
+        let mut builder = app.notification().builder().title(&track.title).body(&track.artist);
+        if let Some(path) = cover {
+            builder = builder.icon(path.to_string_lossy());
+        }
+        if let Err(e) = builder.show() {
+            eprintln!("Failed to show notification: {}", e);
+        }
+    });
+}
+
+/// Confirm a rating made from the tray or a shortcut, while the player isn't in view
+pub fn rating_changed(app: &tauri::AppHandle, status: LikeStatus) {
+    let enabled = app.state::<SettingsState>().0.lock().unwrap().notifications.enabled;
+    if !enabled || main_window_focused(app) {
+        return;
+    }
+    let Some(track) = app.state::<NowPlayingState>().0.lock().unwrap().track.clone() else { return };
+
+    let title = match status {
+        LikeStatus::Like => "Liked",
+        LikeStatus::Dislike => "Disliked",
+        LikeStatus::Indifferent => "Rating removed",
+    };
+    let body = format!("{} — {}", track.title, track.artist);
+    if let Err(e) = app.notification().builder().title(title).body(body).show() {
+        eprintln!("Failed to show notification: {}", e);
+    }
+}
@@ -0,0 +1,149 @@
+use serde::{Deserialize, Serialize};
+use std::sync::Mutex;
+use tauri::State;
+
+/// Track metadata as known by the frontend queue
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct Track {
+    pub video_id: String,
+    pub title: String,
+    pub artist: String,
+    pub album: String,
+    pub thumb_url: String,
+    /// Length in seconds, 0 if unknown
+    pub duration: f64,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RepeatMode {
+    #[default]
+    Off,
+    All,
+    One,
+}
+
+/// The user's rating of a track on YouTube Music
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LikeStatus {
+    #[default]
+    Indifferent,
+    Like,
+    Dislike,
+}
+
+/// Single source of truth for what's playing, fed by the frontend player
+#[derive(Clone, Default, PartialEq, Serialize)]
+pub struct NowPlaying {
+    pub track: Option<Track>,
+    pub is_playing: bool,
+    pub current_time: f64,
+    /// 0-100
+    pub volume: f64,
+    pub shuffle: bool,
+    pub repeat: RepeatMode,
+    /// Looked up on the Rust side when the track changes
+    pub like_status: LikeStatus,
+    /// `None` until known
+    pub in_library: Option<bool>,
+}
+
+pub struct NowPlayingState(pub Mutex<NowPlaying>);
+
+/// The frontend's play queue, mirrored for remote clients
+#[derive(Clone, Default, Serialize)]
+pub struct Queue {
+    pub tracks: Vec<Track>,
+    /// Position of the current track in `tracks`
+    pub index: Option<usize>,
+    /// Bumped on every change, so clients can tell whether they're up to date
+    pub version: u64,
+}
+
+pub struct QueueState(pub Mutex<Queue>);
+
+/// Push state changes to the Rust-side sinks that care about them
+fn on_change(app: &tauri::AppHandle, prev: &NowPlaying, next: &NowPlaying) {
+    let display_changed = prev.track != next.track
+        || prev.is_playing != next.is_playing
+        || prev.shuffle != next.shuffle
+        || prev.repeat != next.repeat
+        || prev.like_status != next.like_status
+        || prev.in_library != next.in_library;
+
+    let track_changed = prev.track.as_ref().map(|t| &t.video_id) != next.track.as_ref().map(|t| &t.video_id);
+    if let Some(track) = next.track.as_ref().filter(|_| track_changed) {
+        crate::notifications::track_changed(app, track);
+        crate::rating::track_changed(app, &track.video_id);
+    }
+    crate::plays::on_update(app, prev, next);
+
+    #[cfg(desktop)]
+    if display_changed {
+        crate::tray::refresh(app, next);
+    }
+    #[cfg(not(desktop))]
+    let _ = display_changed;
+
+    crate::mpd::now_playing_changed(app, prev, next);
+    if prev != next {
+        crate::remote::publish(app, "now-playing", next);
+    }
+}
+
+#[tauri::command]
+pub fn ytm_update_now_playing(
+    app: tauri::AppHandle,
+    state: State<'_, NowPlayingState>,
+    track: Option<Track>,
+    is_playing: bool,
+    current_time: f64,
+    volume: f64,
+    shuffle: bool,
+    repeat: RepeatMode,
+) {
+    let (prev, next) = {
+        let mut np = state.0.lock().unwrap();
+        let prev = np.clone();
+        // Rating belongs to the track, so it carries over until the track changes
+        let same_track = prev.track.as_ref().map(|t| &t.video_id) == track.as_ref().map(|t| &t.video_id);
+        let (like_status, in_library) =
+            if same_track { (prev.like_status, prev.in_library) } else { (LikeStatus::default(), None) };
+        *np = NowPlaying { track, is_playing, current_time, volume, shuffle, repeat, like_status, in_library };
+        (prev, np.clone())
+    };
+    on_change(&app, &prev, &next);
+}
+
+/// Modify the now-playing state if `video_id` is still the current track, notifying sinks
+pub fn update_current(app: &tauri::AppHandle, video_id: &str, f: impl FnOnce(&mut NowPlaying)) {
+    use tauri::Manager;
+    let (prev, next) = {
+        let state = app.state::<NowPlayingState>();
+        let mut np = state.0.lock().unwrap();
+        if np.track.as_ref().map(|t| t.video_id.as_str()) != Some(video_id) {
+            return;
+        }
+        let prev = np.clone();
+        f(&mut np);
+        (prev, np.clone())
+    };
+    if prev != next {
+        on_change(app, &prev, &next);
+    }
+}
+
+#[tauri::command]
+pub fn ytm_update_queue(
+    app: tauri::AppHandle,
+    state: State<'_, QueueState>,
+    tracks: Vec<Track>,
+    index: Option<usize>,
+) {
+    let mut queue = state.0.lock().unwrap();
+    *queue = Queue { tracks, index, version: queue.version + 1 };
+    crate::remote::publish(&app, "queue", &*queue);
+    crate::mpd::queue_changed(&app);
+}
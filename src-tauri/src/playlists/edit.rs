@@ -0,0 +1,226 @@
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use crate::catalog::{self, Playlist};
+use crate::innertube;
+
+/// Videos per `browse/edit_playlist` call when adding many at once
+const ADD_BATCH: usize = 100;
+
+const PRIVACY: [&str; 3] = ["PUBLIC", "UNLISTED", "PRIVATE"];
+
+/// A video added by an edit, with the id of its new playlist entry
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AddedVideo {
+    pub video_id: String,
+    pub set_video_id: String,
+}
+
+/// One playlist entry to remove
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PlaylistEntry {
+    pub video_id: String,
+    pub set_video_id: String,
+}
+
+pub fn check_privacy(privacy: &str) -> Result<(), String> {
+    if PRIVACY.contains(&privacy) {
+        Ok(())
+    } else {
+        Err(format!("Unknown privacy status: {}", privacy))
+    }
+}
+
+/// Browse ids carry a `VL` prefix the edit endpoints don't want
+fn bare_id(playlist_id: &str) -> &str {
+    playlist_id.strip_prefix("VL").unwrap_or(playlist_id)
+}
+
+/// Anything but an explicit success counts as failure, including a response without a status
+fn check_status(res: &Value) -> Result<(), String> {
+    match res["status"].as_str() {
+        Some("STATUS_SUCCEEDED") => Ok(()),
+        status => Err(format!("Playlist edit failed: {}", status.unwrap_or("no status in response"))),
+    }
+}
+
+async fn edit(app: &tauri::AppHandle, playlist_id: &str, actions: Vec<Value>) -> Result<Value, String> {
+    let res = innertube::call(
+        app,
+        "browse/edit_playlist",
+        json!({ "playlistId": bare_id(playlist_id), "actions": actions }),
+    )
+    .await?;
+    check_status(&res)?;
+    Ok(res)
+}
+
+/// Append videos in batches. Unless `allow_duplicates`, ones already in the playlist are skipped.
+pub async fn add_videos(
+    app: &tauri::AppHandle,
+    playlist_id: &str,
+    video_ids: &[String],
+    allow_duplicates: bool,
+) -> Result<Vec<AddedVideo>, String> {
+    let dedupe = if allow_duplicates { "DEDUPE_OPTION_DEFAULT" } else { "DEDUPE_OPTION_SKIP" };
+    let mut added = Vec::new();
+    for batch in video_ids.chunks(ADD_BATCH) {
+        let actions = batch
+            .iter()
+            .map(|id| json!({ "action": "ACTION_ADD_VIDEO", "addedVideoId": id, "dedupeOption": dedupe }))
+            .collect();
+        let res = edit(app, playlist_id, actions).await?;
+        let results = res["playlistEditResults"].as_array().cloned().unwrap_or_default();
+        added.extend(results.iter().filter_map(|r| {
+            let data = &r["playlistEditVideoAddedResultData"];
+            Some(AddedVideo {
+                video_id: data["videoId"].as_str()?.to_string(),
+                set_video_id: data["setVideoId"].as_str().unwrap_or_default().to_string(),
+            })
+        }));
+    }
+    Ok(added)
+}
+
+/// Create a playlist and fill it. `privacy` is `PUBLIC`, `UNLISTED` or `PRIVATE`. Returns the new id.
+pub async fn create(
+    app: &tauri::AppHandle,
+    title: &str,
+    description: &str,
+    privacy: &str,
+    video_ids: &[String],
+) -> Result<String, String> {
+    check_privacy(privacy)?;
+    let first = &video_ids[..video_ids.len().min(ADD_BATCH)];
+    let res = innertube::call(
+        app,
+        "playlist/create",
+        json!({ "title": title, "description": description, "privacyStatus": privacy, "videoIds": first }),
+    )
+    .await?;
+    let id = res["playlistId"].as_str().ok_or("No playlist id in response")?.to_string();
+    add_videos(app, &id, &video_ids[first.len()..], false).await?;
+    Ok(id)
+}
+
+/// A playlist with every entry's `setVideoId`, as needed for removing and moving
+#[tauri::command]
+pub async fn playlist_get(app: tauri::AppHandle, playlist_id: String) -> Result<Playlist, String> {
+    catalog::playlist(&app, &playlist_id).await
+}
+
+#[tauri::command]
+pub async fn playlist_create(
+    app: tauri::AppHandle,
+    title: String,
+    description: Option<String>,
+    privacy: Option<String>,
+    video_ids: Option<Vec<String>>,
+) -> Result<String, String> {
+    if title.trim().is_empty() {
+        return Err("Playlist title can't be empty".to_string());
+    }
+    let privacy = privacy.unwrap_or_else(|| "PRIVATE".to_string());
+    create(&app, &title, &description.unwrap_or_default(), &privacy, &video_ids.unwrap_or_default()).await
+}
+
+/// Change any of the title, description and privacy
+#[tauri::command]
+pub async fn playlist_update(
+    app: tauri::AppHandle,
+    playlist_id: String,
+    title: Option<String>,
+    description: Option<String>,
+    privacy: Option<String>,
+) -> Result<(), String> {
+    let mut actions = Vec::new();
+    if let Some(title) = title {
+        if title.trim().is_empty() {
+            return Err("Playlist title can't be empty".to_string());
+        }
+        actions.push(json!({ "action": "ACTION_SET_PLAYLIST_NAME", "playlistName": title }));
+    }
+    if let Some(description) = description {
+        actions.push(json!({ "action": "ACTION_SET_PLAYLIST_DESCRIPTION", "playlistDescription": description }));
+    }
+    if let Some(privacy) = privacy {
+        check_privacy(&privacy)?;
+        actions.push(json!({ "action": "ACTION_SET_PLAYLIST_PRIVACY", "playlistPrivacy": privacy }));
+    }
+    if actions.is_empty() {
+        return Ok(());
+    }
+    edit(&app, &playlist_id, actions).await.map(|_| ())
+}
+
+#[tauri::command]
+pub async fn playlist_delete(app: tauri::AppHandle, playlist_id: String) -> Result<(), String> {
+    let res = innertube::call(&app, "playlist/delete", json!({ "playlistId": bare_id(&playlist_id) })).await?;
+    check_status(&res)
+}
+
+/// Returns the added entries; with duplicates skipped this may be fewer than requested
+#[tauri::command]
+pub async fn playlist_add(
+    app: tauri::AppHandle,
+    playlist_id: String,
+    video_ids: Vec<String>,
+    allow_duplicates: Option<bool>,
+) -> Result<Vec<AddedVideo>, String> {
+    add_videos(&app, &playlist_id, &video_ids, allow_duplicates.unwrap_or(false)).await
+}
+
+#[tauri::command]
+pub async fn playlist_remove(
+    app: tauri::AppHandle,
+    playlist_id: String,
+    entries: Vec<PlaylistEntry>,
+) -> Result<(), String> {
+    if entries.is_empty() {
+        return Ok(());
+    }
+    let actions = entries
+        .iter()
+        .map(|e| json!({ "action": "ACTION_REMOVE_VIDEO", "setVideoId": e.set_video_id, "removedVideoId": e.video_id }))
+        .collect();
+    edit(&app, &playlist_id, actions).await.map(|_| ())
+}
+
+/// Move an entry right before `before`, or right after `after` (both `setVideoId`s)
+#[tauri::command]
+pub async fn playlist_move(
+    app: tauri::AppHandle,
+    playlist_id: String,
+    set_video_id: String,
+    before: Option<String>,
+    after: Option<String>,
+) -> Result<(), String> {
+    let action = match (before, after) {
+        (Some(successor), None) => json!({
+            "action": "ACTION_MOVE_VIDEO_BEFORE",
+            "setVideoId": set_video_id,
+            "movedSetVideoIdSuccessor": successor,
+        }),
+        (None, Some(predecessor)) => json!({
+            "action": "ACTION_MOVE_VIDEO_AFTER",
+            "setVideoId": set_video_id,
+            "movedSetVideoIdPredecessor": predecessor,
+        }),
+        _ => return Err("Give exactly one of before or after".to_string()),
+    };
+    edit(&app, &playlist_id, vec![action]).await.map(|_| ())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_explicit_success_passes() {
+        assert!(check_status(&json!({ "status": "STATUS_SUCCEEDED" })).is_ok());
+        assert!(check_status(&json!({ "status": "STATUS_FAILED" })).is_err());
+        assert!(check_status(&json!({})).is_err());
+    }
+}
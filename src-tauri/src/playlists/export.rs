@@ -0,0 +1,282 @@
+use serde_json::json;
+use std::path::PathBuf;
+use tauri_plugin_dialog::DialogExt;
+
+use crate::catalog::{self, Playlist};
+use crate::csv;
+
+#[derive(Clone, Copy)]
+enum Format {
+    M3u8,
+    Xspf,
+    Csv,
+    Json,
+}
+
+impl Format {
+    fn parse(s: &str) -> Result<Self, String> {
+        match s.to_ascii_lowercase().as_str() {
+            "m3u8" | "m3u" => Ok(Format::M3u8),
+            "xspf" => Ok(Format::Xspf),
+            "csv" => Ok(Format::Csv),
+            "json" => Ok(Format::Json),
+            other => Err(format!("Unknown playlist format: {}", other)),
+        }
+    }
+
+    fn extension(self) -> &'static str {
+        match self {
+            Format::M3u8 => "m3u8",
+            Format::Xspf => "xspf",
+            Format::Csv => "csv",
+            Format::Json => "json",
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            Format::M3u8 => "M3U8 playlist",
+            Format::Xspf => "XSPF playlist",
+            Format::Csv => "CSV",
+            Format::Json => "JSON",
+        }
+    }
+}
+
+fn watch_url(video_id: &str) -> String {
+    format!("https://music.youtube.com/watch?v={}", video_id)
+}
+
+/// M3U directives are line based, so line breaks inside a field would start a new entry
+fn single_line(s: &str) -> String {
+    s.replace(['\r', '\n'], " ")
+}
+
+fn xml_escape(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&apos;"),
+            // Control characters other than tab and line breaks aren't allowed in XML 1.0
+            c if c.is_control() && !matches!(c, '\t' | '\n' | '\r') => {}
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+/// Extended M3U, UTF-8. Unknown durations are written as -1 per the spec.
+fn to_m3u8(playlist: &Playlist) -> String {
+    let mut out = format!("#EXTM3U\n#PLAYLIST:{}\n", single_line(&playlist.title));
+    for item in &playlist.items {
+        let t = &item.track;
+        let secs = if t.duration > 0.0 { t.duration.round() as i64 } else { -1 };
+        let name = if t.artist.is_empty() { t.title.clone() } else { format!("{} - {}", t.artist, t.title) };
+        out.push_str(&format!("#EXTINF:{},{}\n", secs, single_line(&name)));
+        if !t.album.is_empty() {
+            out.push_str(&format!("#EXTALB:{}\n", single_line(&t.album)));
+        }
+        out.push_str(&watch_url(&t.video_id));
+        out.push('\n');
+    }
+    out
+}
+
+fn to_xspf(playlist: &Playlist) -> String {
+    let mut out = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    out.push_str("<playlist version=\"1\" xmlns=\"http://xspf.org/ns/0/\">\n");
+    out.push_str(&format!("  <title>{}</title>\n", xml_escape(&playlist.title)));
+    out.push_str("  <trackList>\n");
+    for item in &playlist.items {
+        let t = &item.track;
+        out.push_str("    <track>\n");
+        out.push_str(&format!("      <location>{}</location>\n", xml_escape(&watch_url(&t.video_id))));
+        out.push_str(&format!("      <identifier>{}</identifier>\n", xml_escape(&watch_url(&t.video_id))));
+        out.push_str(&format!("      <title>{}</title>\n", xml_escape(&t.title)));
+        if !t.artist.is_empty() {
+            out.push_str(&format!("      <creator>{}</creator>\n", xml_escape(&t.artist)));
+        }
+        if !t.album.is_empty() {
+            out.push_str(&format!("      <album>{}</album>\n", xml_escape(&t.album)));
+        }
+        if t.duration > 0.0 {
+            out.push_str(&format!("      <duration>{}</duration>\n", (t.duration * 1000.0).round() as u64));
+        }
+        if !t.thumb_url.is_empty() {
+            out.push_str(&format!("      <image>{}</image>\n", xml_escape(&t.thumb_url)));
+        }
+        out.push_str("    </track>\n");
+    }
+    out.push_str("  </trackList>\n</playlist>\n");
+    out
+}
+
+/// Columns match what the playlist importer reads back
+fn to_csv(playlist: &Playlist) -> String {
+    let mut out = csv::row(&["title", "artist", "album", "duration", "video_id", "url"]);
+    for item in &playlist.items {
+        let t = &item.track;
+        let duration = if t.duration > 0.0 { format!("{}", t.duration.round() as u64) } else { String::new() };
+        out.push_str(&csv::row(&[
+            t.title.clone(),
+            t.artist.clone(),
+            t.album.clone(),
+            duration,
+            t.video_id.clone(),
+            watch_url(&t.video_id),
+        ]));
+    }
+    out
+}
+
+fn to_json(playlist: &Playlist) -> Result<String, String> {
+    let tracks: Vec<_> = playlist
+        .items
+        .iter()
+        .map(|item| {
+            let t = &item.track;
+            json!({
+                "videoId": t.video_id,
+                "title": t.title,
+                "artist": t.artist,
+                "album": t.album,
+                "duration": t.duration,
+                "url": watch_url(&t.video_id),
+            })
+        })
+        .collect();
+    let doc = json!({ "id": playlist.id, "title": playlist.title, "tracks": tracks });
+    serde_json::to_string_pretty(&doc).map_err(|e| e.to_string())
+}
+
+fn render(playlist: &Playlist, format: Format) -> Result<String, String> {
+    match format {
+        Format::M3u8 => Ok(to_m3u8(playlist)),
+        Format::Xspf => Ok(to_xspf(playlist)),
+        Format::Csv => Ok(to_csv(playlist)),
+        Format::Json => to_json(playlist),
+    }
+}
+
+/// Default name for the save dialog, minus characters Windows won't accept
+fn file_name(title: &str, format: Format) -> String {
+    let stem: String =
+        title.chars().map(|c| if matches!(c, '<' | '>' | ':' | '"' | '/' | '\\' | '|' | '?' | '*') { '_' } else { c }).collect();
+    format!("{}.{}", stem.trim(), format.extension())
+}
+
+async fn ask_path(app: &tauri::AppHandle, title: &str, format: Format) -> Result<Option<PathBuf>, String> {
+    let (tx, rx) = tokio::sync::oneshot::channel();
+    app.dialog()
+        .file()
+        .set_file_name(file_name(title, format))
+        .add_filter(format.label(), &[format.extension()])
+        .save_file(move |path| {
+            let _ = tx.send(path);
+        });
+    match rx.await.map_err(|e| e.to_string())? {
+        Some(path) => path.into_path().map(Some).map_err(|e| e.to_string()),
+        None => Ok(None),
+    }
+}
+
+/// Export a playlist as `m3u8`, `xspf`, `csv` or `json`. Without `path` a save dialog is shown.
+/// Returns the written file, or `None` if the dialog was cancelled.
+#[tauri::command]
+pub async fn export_playlist(
+    app: tauri::AppHandle,
+    playlist_id: String,
+    format: String,
+    path: Option<String>,
+) -> Result<Option<String>, String> {
+    let format = Format::parse(&format)?;
+    let playlist = catalog::playlist(&app, &playlist_id).await?;
+    let text = render(&playlist, format)?;
+
+    let path = match path {
+        Some(path) => PathBuf::from(path),
+        None => match ask_path(&app, &playlist.title, format).await? {
+            Some(path) => path,
+            None => return Ok(None),
+        },
+    };
+    std::fs::write(&path, text).map_err(|e| e.to_string())?;
+    Ok(Some(path.to_string_lossy().into_owned()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::catalog::PlaylistItem;
+    use crate::now_playing::Track;
+
+    fn playlist(title: &str, artist: &str, album: &str) -> Playlist {
+        let track = Track {
+            video_id: "abc_DEF-123".to_string(),
+            title: title.to_string(),
+            artist: artist.to_string(),
+            album: album.to_string(),
+            thumb_url: String::new(),
+            duration: 201.4,
+        };
+        Playlist {
+            id: "PL1".to_string(),
+            title: title.to_string(),
+            items: vec![PlaylistItem { track, set_video_id: String::new() }],
+        }
+    }
+
+    #[test]
+    fn csv_quotes_commas_and_doubles_quotes() {
+        let out = to_csv(&playlist("Hello, \"World\"", "A, B", "Plain"));
+        let line = out.lines().nth(1).unwrap();
+        assert_eq!(
+            line,
+            "\"Hello, \"\"World\"\"\",\"A, B\",Plain,201,abc_DEF-123,https://music.youtube.com/watch?v=abc_DEF-123"
+        );
+        assert!(out.ends_with("\r\n"));
+    }
+
+    #[test]
+    fn csv_quotes_line_breaks() {
+        let out = to_csv(&playlist("Two\nLines", "X", ""));
+        assert!(out.contains("\"Two\nLines\",X,,201"));
+    }
+
+    #[test]
+    fn xml_escape_handles_markup_and_control_characters() {
+        assert_eq!(xml_escape("Tom & Jerry <3 \"quoted\" 'single'"), "Tom &amp; Jerry &lt;3 &quot;quoted&quot; &apos;single&apos;");
+        assert_eq!(xml_escape("bell\u{7}tab\tend\u{0}"), "belltab\tend");
+    }
+
+    #[test]
+    fn m3u_lines_stay_single() {
+        let out = to_m3u8(&playlist("Line\r\nBreak", "Art\nist", "Al\rbum"));
+        assert!(out.contains("#EXTINF:201,Art ist - Line  Break\n"));
+        assert!(out.contains("#EXTALB:Al bum\n"));
+        assert!(out.lines().all(|l| l.starts_with('#') || l.starts_with("https://")));
+    }
+
+    #[test]
+    fn non_ascii_is_kept_in_every_format() {
+        let title = "Ünïcödé 日本語 — Привет";
+        let artist = "Sigur Rós";
+        let p = playlist(title, artist, "Ágætis byrjun");
+        for format in [Format::M3u8, Format::Xspf, Format::Csv, Format::Json] {
+            let out = render(&p, format).unwrap();
+            assert!(out.contains(title), "{} lost the title", format.extension());
+            assert!(out.contains(artist), "{} lost the artist", format.extension());
+        }
+    }
+
+    #[test]
+    fn json_round_trips_special_characters() {
+        let out = to_json(&playlist("a, \"b\" & <c>", "d", "")).unwrap();
+        let doc: serde_json::Value = serde_json::from_str(&out).unwrap();
+        assert_eq!(doc["tracks"][0]["title"], "a, \"b\" & <c>");
+    }
+}
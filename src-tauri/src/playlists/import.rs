@@ -0,0 +1,610 @@
+use serde::Serialize;
+use serde_json::Value;
+use std::path::{Path, PathBuf};
+use tauri::Emitter;
+use tauri_plugin_dialog::DialogExt;
+
+use super::edit;
+use crate::catalog;
+use crate::control::parse_time;
+use crate::csv;
+use crate::links::{self, Link};
+use crate::matching::{self, clean_title, normalize, TrackFields};
+use crate::now_playing::Track;
+
+/// Scores at or above this are added to the playlist without review
+const MATCH_THRESHOLD: f64 = 0.8;
+/// Scores below this aren't worth showing as a suggestion
+const REVIEW_THRESHOLD: f64 = 0.5;
+/// Runners-up kept for rows that need review
+const ALTERNATIVES: usize = 3;
+
+/// A track as described by the imported file
+#[derive(Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SourceTrack {
+    pub title: String,
+    pub artist: String,
+    pub album: String,
+    pub isrc: String,
+    /// Seconds, 0 if unknown
+    pub duration: f64,
+    /// Set when the file already points at YouTube Music
+    pub video_id: String,
+}
+
+#[derive(Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MatchStatus {
+    Matched,
+    Review,
+    Unmatched,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportRow {
+    pub source: SourceTrack,
+    pub status: MatchStatus,
+    /// Confidence of `track` in `0.0..=1.0`
+    pub score: f64,
+    pub track: Option<Track>,
+    pub alternatives: Vec<Track>,
+    pub error: Option<String>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportReport {
+    pub title: String,
+    pub rows: Vec<ImportRow>,
+    pub matched: usize,
+    pub review: usize,
+    pub unmatched: usize,
+    /// Set when a playlist was created from the matched rows
+    pub playlist_id: Option<String>,
+}
+
+#[derive(Serialize, Clone)]
+struct Progress {
+    done: usize,
+    total: usize,
+}
+
+/// "Artist - Title", as used in M3U titles and file names
+fn split_display(display: &str) -> (String, String) {
+    match display.split_once(" - ") {
+        Some((artist, title)) => (artist.trim().to_string(), title.trim().to_string()),
+        None => (String::new(), display.trim().to_string()),
+    }
+}
+
+fn video_id_of(location: &str) -> String {
+    match links::parse(location) {
+        Some(Link::Track(id)) | Some(Link::Playlist { video: Some(id), .. }) => id,
+        _ => String::new(),
+    }
+}
+
+// ===== CSV =====
+
+/// Header aliases, normalized. Covers our own export, Exportify and most spreadsheet layouts.
+const TITLE_COLUMNS: &[&str] = &["title", "track", "track name", "name", "song"];
+const ARTIST_COLUMNS: &[&str] = &["artist", "artists", "artist name", "artist names", "artist name s", "creator"];
+const ALBUM_COLUMNS: &[&str] = &["album", "album name", "album title"];
+const ISRC_COLUMNS: &[&str] = &["isrc"];
+const DURATION_COLUMNS: &[&str] = &["duration", "length", "time", "duration ms", "track duration ms"];
+const VIDEO_COLUMNS: &[&str] = &["video id", "videoid", "url", "link"];
+
+fn parse_csv(text: &str) -> Vec<SourceTrack> {
+    let rows = csv::parse(text);
+    let Some(header) = rows.first() else { return Vec::new() };
+    let header: Vec<String> = header.iter().map(|h| normalize(h)).collect();
+    let column = |aliases: &[&str]| header.iter().position(|h| aliases.contains(&h.as_str()));
+
+    // Without a recognizable header, assume title, artist, album, ISRC, duration
+    let (title, skip) = match column(TITLE_COLUMNS) {
+        Some(i) => (i, 1),
+        None => (0, 0),
+    };
+    let artist = if skip == 1 { column(ARTIST_COLUMNS) } else { Some(1) };
+    let album = if skip == 1 { column(ALBUM_COLUMNS) } else { Some(2) };
+    let isrc = if skip == 1 { column(ISRC_COLUMNS) } else { Some(3) };
+    let duration = if skip == 1 { column(DURATION_COLUMNS) } else { Some(4) };
+    let video = if skip == 1 { column(VIDEO_COLUMNS) } else { None };
+    let millis = duration.is_some_and(|i| skip == 1 && header[i].ends_with("ms"));
+
+    rows.iter()
+        .skip(skip)
+        .filter_map(|row| {
+            let field = |i: Option<usize>| i.and_then(|i| row.get(i)).map(|f| f.trim().to_string()).unwrap_or_default();
+            let title = field(Some(title));
+            if title.is_empty() {
+                return None;
+            }
+            let duration = field(duration);
+            let duration = if millis {
+                duration.parse::<f64>().map(|ms| ms / 1000.0).unwrap_or(0.0)
+            } else {
+                parse_time(&duration).unwrap_or(0.0)
+            };
+            let video = field(video);
+            let video_id = if video.contains("://") { video_id_of(&video) } else { video };
+            Some(SourceTrack { title, artist: field(artist), album: field(album), isrc: field(isrc), duration, video_id })
+        })
+        .collect()
+}
+
+// ===== M3U =====
+
+/// Returns the `#PLAYLIST` name, if any, and the entries
+fn parse_m3u(text: &str) -> (Option<String>, Vec<SourceTrack>) {
+    let mut name = None;
+    let mut tracks = Vec::new();
+    let mut pending = SourceTrack::default();
+
+    for line in text.trim_start_matches('\u{feff}').lines().map(str::trim) {
+        if let Some(info) = line.strip_prefix("#EXTINF:") {
+            let (secs, display) = info.split_once(',').unwrap_or((info, ""));
+            // Attributes like `tvg-id="..."` may follow the duration
+            let secs = secs.split_whitespace().next().unwrap_or_default();
+            pending.duration = secs.parse::<f64>().ok().filter(|d| *d > 0.0).unwrap_or(0.0);
+            (pending.artist, pending.title) = split_display(display);
+        } else if let Some(album) = line.strip_prefix("#EXTALB:") {
+            pending.album = album.trim().to_string();
+        } else if let Some(playlist) = line.strip_prefix("#PLAYLIST:") {
+            name = Some(playlist.trim().to_string());
+        } else if !line.is_empty() && !line.starts_with('#') {
+            let mut track = std::mem::take(&mut pending);
+            track.video_id = video_id_of(line);
+            if track.title.is_empty() && track.video_id.is_empty() {
+                let file = line.rsplit(['/', '\\']).next().unwrap_or(line);
+                let stem = Path::new(file).file_stem().and_then(|s| s.to_str()).unwrap_or(file);
+                (track.artist, track.title) = split_display(stem);
+            }
+            tracks.push(track);
+        }
+    }
+    (name, tracks)
+}
+
+// ===== XSPF =====
+
+fn xml_unescape(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    let mut rest = s;
+    while let Some(amp) = rest.find('&') {
+        out.push_str(&rest[..amp]);
+        rest = &rest[amp..];
+        let Some(semi) = rest.find(';') else { break };
+        let entity = &rest[1..semi];
+        let decoded = match entity {
+            "amp" => Some('&'),
+            "lt" => Some('<'),
+            "gt" => Some('>'),
+            "quot" => Some('"'),
+            "apos" => Some('\''),
+            _ => entity
+                .strip_prefix("#x")
+                .or_else(|| entity.strip_prefix("#X"))
+                .map(|hex| u32::from_str_radix(hex, 16))
+                .or_else(|| entity.strip_prefix('#').map(|dec| dec.parse::<u32>()))
+                .and_then(|n| n.ok())
+                .and_then(char::from_u32),
+        };
+        match decoded {
+            Some(c) => {
+                out.push(c);
+                rest = &rest[semi + 1..];
+            }
+            None => {
+                out.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+/// Text of the first `<name>` element, CDATA and entities decoded
+fn xml_element(xml: &str, name: &str) -> Option<String> {
+    let open = format!("<{}>", name);
+    let start = xml.find(&open)? + open.len();
+    let end = start + xml[start..].find(&format!("</{}>", name))?;
+    let inner = xml[start..end].trim();
+    match inner.strip_prefix("<![CDATA[").and_then(|s| s.strip_suffix("]]>")) {
+        Some(cdata) => Some(cdata.to_string()),
+        None => Some(xml_unescape(inner)),
+    }
+}
+
+fn parse_xspf(text: &str) -> (Option<String>, Vec<SourceTrack>) {
+    let list_start = text.find("<trackList>").unwrap_or(text.len());
+    let name = xml_element(&text[..list_start], "title");
+
+    let tracks = text[list_start..]
+        .split("<track>")
+        .skip(1)
+        .map(|block| {
+            let block = block.split("</track>").next().unwrap_or(block);
+            let field = |name: &str| xml_element(block, name).unwrap_or_default();
+            let location = field("location");
+            let identifier = field("identifier");
+            let video_id = [location, identifier].iter().map(|l| video_id_of(l)).find(|id| !id.is_empty());
+            SourceTrack {
+                title: field("title"),
+                artist: field("creator"),
+                album: field("album"),
+                isrc: String::new(),
+                duration: field("duration").parse::<f64>().map(|ms| ms / 1000.0).unwrap_or(0.0),
+                video_id: video_id.unwrap_or_default(),
+            }
+        })
+        .filter(|t| !t.title.is_empty() || !t.video_id.is_empty())
+        .collect();
+    (name, tracks)
+}
+
+// ===== JSON =====
+
+fn string(v: &Value, keys: &[&str]) -> String {
+    keys.iter().find_map(|k| v[*k].as_str()).unwrap_or_default().trim().to_string()
+}
+
+/// One track object from any of the supported JSON layouts
+fn json_track(v: &Value) -> Option<SourceTrack> {
+    // Spotify playlist export and Web API items wrap the track
+    let t = if v["track"].is_object() { &v["track"] } else { v };
+    let artist = match t["artists"].as_array() {
+        // Web API: artists: [{ name }]
+        Some(artists) => artists.iter().filter_map(|a| a["name"].as_str()).collect::<Vec<_>>().join(", "),
+        None => string(t, &["artistName", "artist"]),
+    };
+    let album = match t["album"].as_object() {
+        Some(album) => album.get("name").and_then(Value::as_str).unwrap_or_default().to_string(),
+        None => string(t, &["albumName", "album"]),
+    };
+    let duration = t["duration_ms"]
+        .as_f64()
+        .map(|ms| ms / 1000.0)
+        .or_else(|| t["duration"].as_f64())
+        .unwrap_or(0.0);
+    let title = match &t["track"] {
+        // Spotify library export: { artist, album, track, uri }
+        Value::String(s) => s.trim().to_string(),
+        _ => string(t, &["trackName", "name", "title"]),
+    };
+    let track = SourceTrack {
+        title,
+        artist,
+        album,
+        isrc: t["external_ids"]["isrc"].as_str().unwrap_or_default().to_string(),
+        duration,
+        video_id: string(t, &["videoId"]),
+    };
+    (!track.title.is_empty() || !track.video_id.is_empty()).then_some(track)
+}
+
+/// Spotify's account data export (`Playlist*.json`, `YourLibrary.json`), Web API dumps and our own JSON export
+fn parse_json(text: &str, playlist_name: Option<&str>) -> Result<(Option<String>, Vec<SourceTrack>), String> {
+    let doc: Value = serde_json::from_str(text).map_err(|e| e.to_string())?;
+
+    if let Some(playlists) = doc["playlists"].as_array() {
+        let playlist = match playlist_name {
+            Some(name) => playlists.iter().find(|p| p["name"].as_str() == Some(name)),
+            None => playlists.first(),
+        }
+        .ok_or("Playlist not found in file")?;
+        let items = playlist["items"].as_array().map(|i| i.iter().filter_map(json_track).collect());
+        return Ok((playlist["name"].as_str().map(str::to_string), items.unwrap_or_default()));
+    }
+
+    let items = ["tracks", "items"].iter().find_map(|k| doc[*k].as_array()).or(doc.as_array());
+    let items = items.ok_or("No tracks found in file")?;
+    Ok((doc["title"].as_str().or(doc["name"].as_str()).map(str::to_string), items.iter().filter_map(json_track).collect()))
+}
+
+// ===== Matching =====
+
+fn confidence(src: &SourceTrack, candidate: &Track) -> f64 {
+    matching::confidence(
+        &TrackFields { title: &src.title, artist: &src.artist, album: &src.album, duration: src.duration },
+        &TrackFields {
+            title: &candidate.title,
+            artist: &candidate.artist,
+            album: &candidate.album,
+            duration: candidate.duration,
+        },
+    )
+}
+
+/// Search candidates for a query, best first
+async fn ranked(app: &tauri::AppHandle, src: &SourceTrack, query: &str) -> Result<Vec<(f64, Track)>, String> {
+    let mut scored: Vec<(f64, Track)> =
+        catalog::search_songs(app, query).await?.into_iter().map(|t| (confidence(src, &t), t)).collect();
+    scored.sort_by(|a, b| b.0.total_cmp(&a.0));
+    Ok(scored)
+}
+
+async fn match_track(app: &tauri::AppHandle, src: SourceTrack) -> ImportRow {
+    let mut row =
+        ImportRow { source: src, status: MatchStatus::Unmatched, score: 0.0, track: None, alternatives: Vec::new(), error: None };
+
+    if !row.source.video_id.is_empty() {
+        let s = &row.source;
+        row.track = Some(Track {
+            video_id: s.video_id.clone(),
+            title: s.title.clone(),
+            artist: s.artist.clone(),
+            album: s.album.clone(),
+            thumb_url: String::new(),
+            duration: s.duration,
+        });
+        row.status = MatchStatus::Matched;
+        row.score = 1.0;
+        return row;
+    }
+
+    // YouTube Music sometimes finds the exact recording by ISRC; only trust a confident hit
+    let mut candidates = Vec::new();
+    if !row.source.isrc.is_empty() {
+        if let Ok(found) = ranked(app, &row.source, &row.source.isrc).await {
+            candidates = found.into_iter().filter(|(score, _)| *score >= MATCH_THRESHOLD).collect();
+        }
+    }
+    if candidates.is_empty() {
+        let query = format!("{} {}", clean_title(&row.source.title), row.source.artist);
+        match ranked(app, &row.source, query.trim()).await {
+            Ok(found) => candidates = found,
+            Err(e) => row.error = Some(e),
+        }
+    }
+
+    let mut candidates = candidates.into_iter().filter(|(score, _)| *score >= REVIEW_THRESHOLD);
+    if let Some((score, track)) = candidates.next() {
+        row.status = if score >= MATCH_THRESHOLD { MatchStatus::Matched } else { MatchStatus::Review };
+        row.score = score;
+        row.track = Some(track);
+        row.alternatives = candidates.take(ALTERNATIVES).map(|(_, t)| t).collect();
+    }
+    row
+}
+
+// ===== Command =====
+
+async fn ask_path(app: &tauri::AppHandle) -> Result<Option<PathBuf>, String> {
+    let (tx, rx) = tokio::sync::oneshot::channel();
+    app.dialog()
+        .file()
+        .add_filter("Playlists", &["csv", "m3u", "m3u8", "xspf", "json"])
+        .pick_file(move |path| {
+            let _ = tx.send(path);
+        });
+    match rx.await.map_err(|e| e.to_string())? {
+        Some(path) => path.into_path().map(Some).map_err(|e| e.to_string()),
+        None => Ok(None),
+    }
+}
+
+fn read_source(path: &Path, playlist_name: Option<&str>) -> Result<(Option<String>, Vec<SourceTrack>), String> {
+    let text = std::fs::read_to_string(path).map_err(|e| e.to_string())?;
+    let ext = path.extension().and_then(|e| e.to_str()).unwrap_or_default().to_ascii_lowercase();
+    match ext.as_str() {
+        "csv" => Ok((None, parse_csv(&text))),
+        "m3u" | "m3u8" => Ok(parse_m3u(&text)),
+        "xspf" => Ok(parse_xspf(&text)),
+        "json" => parse_json(&text, playlist_name),
+        other => Err(format!("Unsupported playlist file: .{}", other)),
+    }
+}
+
+/// Match every track of a CSV, M3U, XSPF or Spotify JSON file against YouTube Music. Without
+/// `path` an open dialog is shown; returns `None` if it was cancelled. With `create`, a playlist
+/// is made from the confidently matched rows; the report lists the rest for review.
+#[tauri::command]
+pub async fn import_playlist(
+    app: tauri::AppHandle,
+    path: Option<String>,
+    playlist_name: Option<String>,
+    title: Option<String>,
+    create: bool,
+    privacy: Option<String>,
+) -> Result<Option<ImportReport>, String> {
+    let privacy = privacy.unwrap_or_else(|| "PRIVATE".to_string());
+    edit::check_privacy(&privacy)?;
+
+    let path = match path {
+        Some(path) => PathBuf::from(path),
+        None => match ask_path(&app).await? {
+            Some(path) => path,
+            None => return Ok(None),
+        },
+    };
+    let (name, sources) = read_source(&path, playlist_name.as_deref())?;
+    if sources.is_empty() {
+        return Err("No tracks found in file".to_string());
+    }
+    let title = title.or(name).filter(|t| !t.trim().is_empty()).unwrap_or_else(|| {
+        path.file_stem().map(|s| s.to_string_lossy().into_owned()).unwrap_or_else(|| "Imported playlist".to_string())
+    });
+
+    let total = sources.len();
+    let mut rows = Vec::with_capacity(total);
+    for (i, src) in sources.into_iter().enumerate() {
+        rows.push(match_track(&app, src).await);
+        let _ = app.emit("playlist-import-progress", Progress { done: i + 1, total });
+    }
+
+    let count = |status| rows.iter().filter(|r| r.status == status).count();
+    let mut report = ImportReport {
+        title,
+        matched: count(MatchStatus::Matched),
+        review: count(MatchStatus::Review),
+        unmatched: count(MatchStatus::Unmatched),
+        rows,
+        playlist_id: None,
+    };
+
+    if create {
+        let video_ids: Vec<String> = report
+            .rows
+            .iter()
+            .filter(|r| r.status == MatchStatus::Matched)
+            .filter_map(|r| r.track.as_ref().map(|t| t.video_id.clone()))
+            .collect();
+        if video_ids.is_empty() {
+            return Err("No tracks matched confidently enough to create a playlist".to_string());
+        }
+        let description = format!("Imported from {}", path.file_name().unwrap_or_default().to_string_lossy());
+        let id = edit::create(&app, &report.title, &description, &privacy, &video_ids).await?;
+        report.playlist_id = Some(id);
+    }
+    Ok(Some(report))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn csv_handles_quotes_and_embedded_commas() {
+        let text = "title,artist,album,duration,video_id,url\r\n\
+                    \"Hello, \"\"World\"\"\",\"Crosby, Stills\",Déjà Vu,3:25,abc_DEF-123,https://music.youtube.com/watch?v=abc_DEF-123\r\n";
+        let tracks = parse_csv(text);
+        assert_eq!(tracks.len(), 1);
+        let t = &tracks[0];
+        assert_eq!(t.title, "Hello, \"World\"");
+        assert_eq!(t.artist, "Crosby, Stills");
+        assert_eq!(t.album, "Déjà Vu");
+        assert_eq!(t.duration, 205.0);
+        assert_eq!(t.video_id, "abc_DEF-123");
+    }
+
+    #[test]
+    fn csv_reads_exportify_headers_with_millisecond_durations() {
+        let text = "\"Track URI\",\"Track Name\",\"Artist Name(s)\",\"Album Name\",\"Album Artist Name(s)\",\"Track Duration (ms)\",\"ISRC\"\n\
+                    \"spotify:track:1\",\"One More Time\",\"Daft Punk\",\"Discovery\",\"Daft Punk\",\"320357\",\"GBDUW0000053\"\n";
+        let tracks = parse_csv(text);
+        assert_eq!(tracks.len(), 1);
+        let t = &tracks[0];
+        assert_eq!((t.title.as_str(), t.artist.as_str(), t.album.as_str()), ("One More Time", "Daft Punk", "Discovery"));
+        assert_eq!(t.isrc, "GBDUW0000053");
+        assert!((t.duration - 320.357).abs() < 1e-9);
+        assert!(t.video_id.is_empty());
+    }
+
+    #[test]
+    fn csv_reads_second_durations_and_headerless_files() {
+        let tracks = parse_csv("name,artist,length\nSong,Band,245\n,Skipped,1\n");
+        assert_eq!(tracks.len(), 1);
+        assert_eq!(tracks[0].duration, 245.0);
+
+        // Without a header: title, artist, album, ISRC, duration
+        let tracks = parse_csv("Yellow,Coldplay,Parachutes,GBAYE0000351,4:29\n");
+        let t = &tracks[0];
+        assert_eq!((t.title.as_str(), t.artist.as_str(), t.isrc.as_str()), ("Yellow", "Coldplay", "GBAYE0000351"));
+        assert_eq!(t.duration, 269.0);
+    }
+
+    #[test]
+    fn m3u_splits_extinf_attributes_and_artist() {
+        let text = "#EXTM3U\n\
+                    #PLAYLIST:Mix\n\
+                    #EXTINF:123 tvg-id=\"x\" tvg-logo=\"y\",Daft Punk - One More Time\n\
+                    #EXTALB:Discovery\n\
+                    https://music.youtube.com/watch?v=abc123\n\
+                    #EXTINF:-1,Just A Title\n\
+                    song.mp3\n\
+                    /music/Some Artist - Some Track.flac\n";
+        let (name, tracks) = parse_m3u(text);
+        assert_eq!(name.as_deref(), Some("Mix"));
+        assert_eq!(tracks.len(), 3);
+
+        let t = &tracks[0];
+        assert_eq!((t.artist.as_str(), t.title.as_str(), t.album.as_str()), ("Daft Punk", "One More Time", "Discovery"));
+        assert_eq!(t.duration, 123.0);
+        assert_eq!(t.video_id, "abc123");
+
+        let t = &tracks[1];
+        assert_eq!((t.artist.as_str(), t.title.as_str()), ("", "Just A Title"));
+        assert_eq!(t.duration, 0.0);
+
+        // No #EXTINF: fall back to the file name
+        let t = &tracks[2];
+        assert_eq!((t.artist.as_str(), t.title.as_str()), ("Some Artist", "Some Track"));
+    }
+
+    #[test]
+    fn xspf_decodes_entities_and_cdata() {
+        let text = r#"<?xml version="1.0" encoding="UTF-8"?>
+<playlist version="1" xmlns="http://xspf.org/ns/0/">
+  <title>Rock &amp; Roll</title>
+  <trackList>
+    <track>
+      <location>https://music.youtube.com/watch?v=vid1</location>
+      <title>Caf&#233; &#x263A; &lt;3</title>
+      <creator><![CDATA[AC/DC & <friends>]]></creator>
+      <album>Live</album>
+      <duration>201000</duration>
+    </track>
+    <track>
+      <title>Broken &bogus; entity</title>
+    </track>
+  </trackList>
+</playlist>"#;
+        let (name, tracks) = parse_xspf(text);
+        assert_eq!(name.as_deref(), Some("Rock & Roll"));
+        assert_eq!(tracks.len(), 2);
+        let t = &tracks[0];
+        assert_eq!(t.title, "Café ☺ <3");
+        assert_eq!(t.artist, "AC/DC & <friends>");
+        assert_eq!(t.duration, 201.0);
+        assert_eq!(t.video_id, "vid1");
+        assert_eq!(tracks[1].title, "Broken &bogus; entity");
+    }
+
+    #[test]
+    fn json_reads_spotify_account_export() {
+        let text = r#"{"playlists": [
+            {"name": "Road", "items": [{"track": {"trackName": "T1", "artistName": "A1", "albumName": "B1"}}]},
+            {"name": "Other", "items": [{"track": {"trackName": "T2", "artistName": "A2", "albumName": "B2"}}]}
+        ]}"#;
+        let (name, tracks) = parse_json(text, None).unwrap();
+        assert_eq!(name.as_deref(), Some("Road"));
+        assert_eq!((tracks[0].title.as_str(), tracks[0].artist.as_str(), tracks[0].album.as_str()), ("T1", "A1", "B1"));
+
+        let (name, tracks) = parse_json(text, Some("Other")).unwrap();
+        assert_eq!(name.as_deref(), Some("Other"));
+        assert_eq!(tracks[0].title, "T2");
+        assert!(parse_json(text, Some("Missing")).is_err());
+
+        // YourLibrary.json names the title field "track"
+        let (_, tracks) = parse_json(r#"{"tracks": [{"artist": "A", "album": "B", "track": "C"}]}"#, None).unwrap();
+        assert_eq!((tracks[0].title.as_str(), tracks[0].artist.as_str()), ("C", "A"));
+    }
+
+    #[test]
+    fn json_reads_spotify_web_api_items() {
+        let text = r#"{"items": [{"track": {
+            "name": "Song", "artists": [{"name": "X"}, {"name": "Y"}], "album": {"name": "Album"},
+            "duration_ms": 180000, "external_ids": {"isrc": "US1234567890"}
+        }}]}"#;
+        let (_, tracks) = parse_json(text, None).unwrap();
+        let t = &tracks[0];
+        assert_eq!((t.title.as_str(), t.artist.as_str(), t.album.as_str()), ("Song", "X, Y", "Album"));
+        assert_eq!(t.duration, 180.0);
+        assert_eq!(t.isrc, "US1234567890");
+    }
+
+    #[test]
+    fn json_reads_our_export_with_second_durations() {
+        let text = r#"{"id": "PL1", "title": "Mine", "tracks": [
+            {"videoId": "v1", "title": "T", "artist": "A", "album": "", "duration": 200.0}
+        ]}"#;
+        let (name, tracks) = parse_json(text, None).unwrap();
+        assert_eq!(name.as_deref(), Some("Mine"));
+        assert_eq!(tracks[0].video_id, "v1");
+        assert_eq!(tracks[0].duration, 200.0);
+    }
+}
@@ -0,0 +1,9 @@
+mod edit;
+mod export;
+mod import;
+
+pub use edit::{
+    playlist_add, playlist_create, playlist_delete, playlist_get, playlist_move, playlist_remove, playlist_update,
+};
+pub use export::export_playlist;
+pub use import::import_playlist;
@@ -0,0 +1,97 @@
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tauri::Manager;
+
+use crate::now_playing::{NowPlaying, Track};
+use crate::{history, scrobble};
+
+/// Position jumps bigger than this between two updates are seeks, not listening
+const MAX_TICK_SECS: f64 = 5.0;
+
+/// One listen of a track, from when it started until the player moved on
+#[derive(Clone)]
+pub struct Play {
+    pub track: Track,
+    /// Unix seconds
+    pub started_at: i64,
+    /// Seconds actually played, seeks excluded
+    pub listened: f64,
+    /// Last known playback position
+    pub position: f64,
+}
+
+impl Play {
+    /// Played through to the end (or near enough that the next update would have been past it)
+    pub fn completed(&self) -> bool {
+        self.track.duration > 0.0 && self.position >= self.track.duration - MAX_TICK_SECS
+    }
+}
+
+pub struct PlaysState(pub Mutex<Option<Play>>);
+
+pub fn unix_now() -> i64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs() as i64)
+}
+
+fn start(track: &Track, position: f64) -> Play {
+    Play { track: track.clone(), started_at: unix_now(), listened: 0.0, position }
+}
+
+/// `moved_on` is false when the play ended because the app quit, which isn't a skip
+fn finished(app: &tauri::AppHandle, play: Play, moved_on: bool) {
+    history::record(app, &play, moved_on && !play.completed());
+    scrobble::play_finished(app, &play);
+}
+
+fn started(app: &tauri::AppHandle, play: &Play) {
+    scrobble::play_started(app, play);
+}
+
+/// Follow playback updates, splitting them into plays
+pub fn on_update(app: &tauri::AppHandle, prev: &NowPlaying, next: &NowPlaying) {
+    let (ended, began) = {
+        let state = app.state::<PlaysState>();
+        let mut current = state.0.lock().unwrap();
+        let same_track = current.as_ref().map(|p| &p.track.video_id) == next.track.as_ref().map(|t| &t.video_id);
+        // Repeat-one wraps back to the start without changing the track
+        let looped = current.as_ref().is_some_and(|play| {
+            play.track.duration > 0.0
+                && play.position >= play.track.duration - MAX_TICK_SECS
+                && next.current_time < MAX_TICK_SECS
+        });
+
+        match &next.track {
+            Some(_) if same_track && !looped => {
+                if let Some(play) = current.as_mut() {
+                    let delta = next.current_time - play.position;
+                    if prev.is_playing && delta > 0.0 && delta < MAX_TICK_SECS {
+                        play.listened += delta;
+                    }
+                    play.position = next.current_time;
+                }
+                (None, None)
+            }
+            Some(track) => {
+                let new = start(track, next.current_time);
+                (current.replace(new.clone()), Some(new))
+            }
+            None => (current.take(), None),
+        }
+    };
+
+    if let Some(play) = ended {
+        finished(app, play, true);
+    }
+    if let Some(play) = began {
+        started(app, &play);
+    }
+}
+
+/// End the current play, e.g. when the app quits mid-track
+pub fn finish_current(app: &tauri::AppHandle) {
+    let Some(state) = app.try_state::<PlaysState>() else { return };
+    let ended = state.0.lock().unwrap().take();
+    if let Some(play) = ended {
+        finished(app, play, false);
+    }
+}
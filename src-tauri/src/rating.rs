@@ -0,0 +1,215 @@
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::sync::Mutex;
+use tauri::{Manager, State};
+
+use crate::innertube;
+use crate::now_playing::{self, LikeStatus, NowPlayingState};
+
+/// Feedback tokens that add a track to or remove it from the library. They're fixed per track.
+#[derive(Clone, Default)]
+pub struct LibraryTokens {
+    video_id: String,
+    add: Option<String>,
+    remove: Option<String>,
+}
+
+/// Library tokens of the last track looked up
+#[derive(Default)]
+pub struct RatingState(pub Mutex<LibraryTokens>);
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TrackRating {
+    pub like_status: LikeStatus,
+    /// `None` if YouTube Music offers no library toggle for the track
+    pub in_library: Option<bool>,
+}
+
+/// The `playlistPanelVideoRenderer` for `video_id` anywhere in a `next` response
+fn find_panel_video<'a>(v: &'a Value, video_id: &str) -> Option<&'a Value> {
+    match v {
+        Value::Object(map) => {
+            if let Some(r) = map.get("playlistPanelVideoRenderer") {
+                if r["videoId"].as_str() == Some(video_id) {
+                    return Some(r);
+                }
+            }
+            map.values().find_map(|v| find_panel_video(v, video_id))
+        }
+        Value::Array(items) => items.iter().find_map(|v| find_panel_video(v, video_id)),
+        _ => None,
+    }
+}
+
+/// Library state and tokens from the track's menu. The default endpoint of the toggle does
+/// whatever the icon suggests: add when it shows `LIBRARY_ADD`, remove otherwise.
+fn parse_library(renderer: &Value, video_id: &str) -> (Option<bool>, LibraryTokens) {
+    let mut tokens = LibraryTokens { video_id: video_id.to_string(), ..Default::default() };
+    let items = renderer["menu"]["menuRenderer"]["items"].as_array().cloned().unwrap_or_default();
+    for item in &items {
+        let toggle = &item["toggleMenuServiceItemRenderer"];
+        let icon = toggle["defaultIcon"]["iconType"].as_str().unwrap_or_default();
+        if !icon.starts_with("LIBRARY_") {
+            continue;
+        }
+        let token = |endpoint: &str| toggle[endpoint]["feedbackEndpoint"]["feedbackToken"].as_str().map(str::to_string);
+        let saved = icon != "LIBRARY_ADD";
+        if saved {
+            (tokens.remove, tokens.add) = (token("defaultServiceEndpoint"), token("toggledServiceEndpoint"));
+        } else {
+            (tokens.add, tokens.remove) = (token("defaultServiceEndpoint"), token("toggledServiceEndpoint"));
+        }
+        return (Some(saved), tokens);
+    }
+    (None, tokens)
+}
+
+fn parse_like(res: &Value, panel: Option<&Value>) -> LikeStatus {
+    let overlay = res["playerOverlays"]["playerOverlayRenderer"]["actions"]
+        .as_array()
+        .and_then(|actions| actions.iter().find_map(|a| a["likeButtonRenderer"]["likeStatus"].as_str()));
+    let panel = panel.and_then(|p| p["menu"]["menuRenderer"]["topLevelButtons"][0]["likeButtonRenderer"]["likeStatus"].as_str());
+    match overlay.or(panel) {
+        Some("LIKE") => LikeStatus::Like,
+        Some("DISLIKE") => LikeStatus::Dislike,
+        _ => LikeStatus::Indifferent,
+    }
+}
+
+/// Look up a track's rating and library state, caching its library tokens
+async fn fetch(app: &tauri::AppHandle, video_id: &str) -> Result<TrackRating, String> {
+    let res = innertube::call(app, "next", json!({ "videoId": video_id, "isAudioOnly": true })).await?;
+    let panel = find_panel_video(&res, video_id);
+    let (in_library, tokens) = panel.map(|p| parse_library(p, video_id)).unwrap_or_default();
+    *app.state::<RatingState>().0.lock().unwrap() = tokens;
+    Ok(TrackRating { like_status: parse_like(&res, panel), in_library })
+}
+
+/// Refresh the rating shown for a newly started track
+pub fn track_changed(app: &tauri::AppHandle, video_id: &str) {
+    if video_id.is_empty() {
+        return;
+    }
+    let app = app.clone();
+    let video_id = video_id.to_string();
+    tauri::async_runtime::spawn(async move {
+        match fetch(&app, &video_id).await {
+            Ok(rating) => now_playing::update_current(&app, &video_id, |np| {
+                np.like_status = rating.like_status;
+                np.in_library = rating.in_library;
+            }),
+            Err(e) => eprintln!("Failed to look up rating for {}: {}", video_id, e),
+        }
+    });
+}
+
+async fn rate(app: &tauri::AppHandle, video_id: &str, status: LikeStatus) -> Result<(), String> {
+    let endpoint = match status {
+        LikeStatus::Like => "like/like",
+        LikeStatus::Dislike => "like/dislike",
+        LikeStatus::Indifferent => "like/removelike",
+    };
+    innertube::call(app, endpoint, json!({ "target": { "videoId": video_id } })).await?;
+    now_playing::update_current(app, video_id, |np| np.like_status = status);
+    Ok(())
+}
+
+async fn set_in_library(app: &tauri::AppHandle, video_id: &str, add: bool) -> Result<(), String> {
+    let cached = app.state::<RatingState>().0.lock().unwrap().clone();
+    let tokens = if cached.video_id == video_id {
+        cached
+    } else {
+        fetch(app, video_id).await?;
+        app.state::<RatingState>().0.lock().unwrap().clone()
+    };
+    let token = if add { tokens.add } else { tokens.remove };
+    let token = token.ok_or("YouTube Music offers no library toggle for this track")?;
+
+    let res = innertube::call(app, "feedback", json!({ "feedbackTokens": [token] })).await?;
+    if res["feedbackResponses"][0]["isProcessed"].as_bool() == Some(false) {
+        return Err("YouTube Music didn't accept the library change".to_string());
+    }
+    now_playing::update_current(app, video_id, |np| np.in_library = Some(add));
+    Ok(())
+}
+
+fn current_track(app: &tauri::AppHandle) -> Option<(String, LikeStatus, Option<bool>)> {
+    let np = app.state::<NowPlayingState>().0.lock().unwrap().clone();
+    np.track.map(|t| (t.video_id, np.like_status, np.in_library))
+}
+
+fn target(app: &tauri::AppHandle, video_id: Option<String>) -> Result<String, String> {
+    video_id
+        .filter(|id| !id.is_empty())
+        .or_else(|| current_track(app).map(|(id, _, _)| id))
+        .ok_or_else(|| "Nothing is playing".to_string())
+}
+
+/// Rate the current track `status`, or clear the rating if it already is. For the tray and shortcuts.
+fn toggle_rating(app: &tauri::AppHandle, status: LikeStatus) {
+    let Some((video_id, current, _)) = current_track(app) else { return };
+    let status = if current == status { LikeStatus::Indifferent } else { status };
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        match rate(&app, &video_id, status).await {
+            Ok(()) => crate::notifications::rating_changed(&app, status),
+            Err(e) => eprintln!("Failed to rate {}: {}", video_id, e),
+        }
+    });
+}
+
+pub fn toggle_like(app: &tauri::AppHandle) {
+    toggle_rating(app, LikeStatus::Like);
+}
+
+pub fn toggle_dislike(app: &tauri::AppHandle) {
+    toggle_rating(app, LikeStatus::Dislike);
+}
+
+pub fn toggle_library(app: &tauri::AppHandle) {
+    let Some((video_id, _, Some(in_library))) = current_track(app) else { return };
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        if let Err(e) = set_in_library(&app, &video_id, !in_library).await {
+            eprintln!("Failed to update library for {}: {}", video_id, e);
+        }
+    });
+}
+
+/// Rating and library state of a track, the current one by default
+#[tauri::command]
+pub async fn track_rating(app: tauri::AppHandle, video_id: Option<String>) -> Result<TrackRating, String> {
+    let video_id = target(&app, video_id)?;
+    let rating = fetch(&app, &video_id).await?;
+    now_playing::update_current(&app, &video_id, |np| {
+        np.like_status = rating.like_status;
+        np.in_library = rating.in_library;
+    });
+    Ok(rating)
+}
+
+/// Like, dislike or (with `indifferent`) clear the rating of a track, the current one by default
+#[tauri::command]
+pub async fn rate_track(app: tauri::AppHandle, video_id: Option<String>, status: LikeStatus) -> Result<(), String> {
+    let video_id = target(&app, video_id)?;
+    rate(&app, &video_id, status).await
+}
+
+#[tauri::command]
+pub async fn library_add(app: tauri::AppHandle, video_id: Option<String>) -> Result<(), String> {
+    let video_id = target(&app, video_id)?;
+    set_in_library(&app, &video_id, true).await
+}
+
+#[tauri::command]
+pub async fn library_remove(app: tauri::AppHandle, video_id: Option<String>) -> Result<(), String> {
+    let video_id = target(&app, video_id)?;
+    set_in_library(&app, &video_id, false).await
+}
+
+/// Current rating, for sinks that only care whether the track is liked
+pub fn is_liked(state: &State<'_, NowPlayingState>, video_id: &str) -> bool {
+    let np = state.0.lock().unwrap();
+    np.like_status == LikeStatus::Like && np.track.as_ref().is_some_and(|t| t.video_id == video_id)
+}
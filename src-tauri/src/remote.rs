@@ -0,0 +1,376 @@
+use futures_util::{SinkExt, StreamExt};
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+use tauri::Manager;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::{broadcast, watch};
+use tokio_tungstenite::tungstenite::handshake::derive_accept_key;
+use tokio_tungstenite::tungstenite::protocol::Role;
+use tokio_tungstenite::tungstenite::Message;
+use tokio_tungstenite::WebSocketStream;
+
+use crate::control::{self, Request, Response};
+use crate::listener::Listener;
+use crate::settings::RemoteSettings;
+
+const MAX_HEAD: usize = 16 * 1024;
+const MAX_BODY: usize = 64 * 1024;
+/// How long a client gets to send its request before the connection is dropped
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Commands that only read state and may be sent as GET
+const READ_COMMANDS: [&str; 3] = ["now-playing", "queue", "search"];
+
+pub struct RemoteState {
+    /// State pushes for connected WebSocket clients, already serialized
+    events: broadcast::Sender<String>,
+    server: Listener,
+}
+
+impl Default for RemoteState {
+    fn default() -> Self {
+        Self { events: broadcast::channel(64).0, server: Listener::default() }
+    }
+}
+
+/// Push a `{ type, data }` message to every WebSocket client
+pub fn publish(app: &tauri::AppHandle, kind: &str, data: impl Serialize) {
+    let Some(state) = app.try_state::<RemoteState>() else { return };
+    if state.events.receiver_count() == 0 {
+        return;
+    }
+    let _ = state.events.send(json!({ "type": kind, "data": data }).to_string());
+}
+
+/// Feed of the messages sent by [`publish`]
+fn subscribe(app: &tauri::AppHandle) -> broadcast::Receiver<String> {
+    app.state::<RemoteState>().events.subscribe()
+}
+
+/// (Re)start the server with `cfg`, or stop it if disabled
+pub async fn restart(app: &tauri::AppHandle, cfg: &RemoteSettings) -> Result<(), String> {
+    let addr = cfg.enabled.then_some((cfg.bind_address.as_str(), cfg.port));
+    let (handle, token) = (app.clone(), Arc::<str>::from(cfg.token.as_str()));
+    app.state::<RemoteState>()
+        .server
+        .restart("Remote control", addr, |listener, shutdown| serve(handle, listener, token, shutdown))
+        .await
+}
+
+async fn serve(app: tauri::AppHandle, listener: TcpListener, token: Arc<str>, mut shutdown: watch::Receiver<()>) {
+    loop {
+        tokio::select! {
+            accepted = listener.accept() => {
+                let Ok((stream, _)) = accepted else { continue };
+                let (app, token, shutdown) = (app.clone(), token.clone(), shutdown.clone());
+                tauri::async_runtime::spawn(async move {
+                    if let Err(e) = handle(app, &token, stream, shutdown).await {
+                        eprintln!("Remote control connection failed: {}", e);
+                    }
+                });
+            }
+            _ = shutdown.changed() => return,
+        }
+    }
+}
+
+struct HttpRequest {
+    method: String,
+    path: String,
+    query: HashMap<String, String>,
+    /// Lowercased names
+    headers: HashMap<String, String>,
+    body: Vec<u8>,
+}
+
+async fn read_request(stream: &mut TcpStream) -> Result<HttpRequest, String> {
+    let mut buf = Vec::new();
+    let mut chunk = [0u8; 4096];
+    let head_end = loop {
+        let n = stream.read(&mut chunk).await.map_err(|e| e.to_string())?;
+        if n == 0 {
+            return Err("Connection closed".to_string());
+        }
+        buf.extend_from_slice(&chunk[..n]);
+        if let Some(pos) = buf.windows(4).position(|w| w == b"\r\n\r\n") {
+            break pos;
+        }
+        if buf.len() > MAX_HEAD {
+            return Err("Request head too large".to_string());
+        }
+    };
+
+    let head = String::from_utf8_lossy(&buf[..head_end]).to_string();
+    let mut lines = head.lines();
+    let mut request_line = lines.next().unwrap_or_default().split_whitespace();
+    let method = request_line.next().unwrap_or_default().to_string();
+    let target = request_line.next().unwrap_or("/");
+    let url = reqwest::Url::parse(&format!("http://localhost{}", target)).map_err(|e| e.to_string())?;
+
+    let headers: HashMap<String, String> = lines
+        .filter_map(|l| l.split_once(':'))
+        .map(|(k, v)| (k.trim().to_ascii_lowercase(), v.trim().to_string()))
+        .collect();
+
+    let length: usize = headers.get("content-length").and_then(|v| v.parse().ok()).unwrap_or(0);
+    if length > MAX_BODY {
+        return Err("Request body too large".to_string());
+    }
+    let mut body = buf[head_end + 4..].to_vec();
+    while body.len() < length {
+        let n = stream.read(&mut chunk).await.map_err(|e| e.to_string())?;
+        if n == 0 {
+            break;
+        }
+        body.extend_from_slice(&chunk[..n]);
+    }
+    body.truncate(length);
+
+    Ok(HttpRequest {
+        method,
+        path: url.path().to_string(),
+        query: url.query_pairs().into_owned().collect(),
+        headers,
+        body,
+    })
+}
+
+/// Compare without bailing on the first differing byte
+fn token_matches(given: &str, expected: &str) -> bool {
+    given.len() == expected.len()
+        && given.bytes().zip(expected.bytes()).fold(0, |acc, (a, b)| acc | (a ^ b)) == 0
+}
+
+fn authorized(req: &HttpRequest, token: &str) -> bool {
+    let given = req
+        .headers
+        .get("authorization")
+        .and_then(|h| h.strip_prefix("Bearer "))
+        .or_else(|| req.query.get("token").map(String::as_str))
+        .unwrap_or_default();
+    token_matches(given, token)
+}
+
+async fn respond(stream: &mut TcpStream, status: &str, body: &Value) -> Result<(), String> {
+    let body = if body.is_null() { String::new() } else { body.to_string() };
+    let head = format!(
+        "HTTP/1.1 {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\
+         Access-Control-Allow-Origin: *\r\nAccess-Control-Allow-Headers: Authorization, Content-Type\r\n\
+         Access-Control-Allow-Methods: GET, POST, OPTIONS\r\nConnection: close\r\n\r\n",
+        status,
+        body.len()
+    );
+    stream.write_all(head.as_bytes()).await.map_err(|e| e.to_string())?;
+    stream.write_all(body.as_bytes()).await.map_err(|e| e.to_string())
+}
+
+fn error_body(message: &str) -> Value {
+    json!({ "ok": false, "data": null, "error": message })
+}
+
+/// Map `/api/<command>` onto a control command. GET arguments come from `?q=`, POST ones from `{ "args": [...] }`.
+fn to_command(req: &HttpRequest) -> Result<Request, (&'static str, String)> {
+    let command = req
+        .path
+        .strip_prefix("/api/")
+        .filter(|c| !c.is_empty() && !c.contains('/'))
+        .ok_or(("404 Not Found", "Unknown endpoint".to_string()))?
+        .to_string();
+
+    match req.method.as_str() {
+        "GET" if READ_COMMANDS.contains(&command.as_str()) => {
+            let args = req.query.get("q").map(|q| vec![q.clone()]).unwrap_or_default();
+            Ok(Request { command, args })
+        }
+        "POST" => {
+            let args = if req.body.is_empty() {
+                Vec::new()
+            } else {
+                let body: Value = serde_json::from_slice(&req.body)
+                    .map_err(|e| ("400 Bad Request", format!("Invalid JSON: {}", e)))?;
+                serde_json::from_value(body["args"].clone()).unwrap_or_default()
+            };
+            Ok(Request { command, args })
+        }
+        _ => Err(("405 Method Not Allowed", format!("Use POST for {}", command))),
+    }
+}
+
+async fn handle(app: tauri::AppHandle, token: &str, mut stream: TcpStream, shutdown: watch::Receiver<()>) -> Result<(), String> {
+    let req = tokio::time::timeout(REQUEST_TIMEOUT, read_request(&mut stream))
+        .await
+        .map_err(|_| "Timed out waiting for the request".to_string())??;
+
+    // CORS preflight carries no credentials
+    if req.method == "OPTIONS" {
+        return respond(&mut stream, "204 No Content", &Value::Null).await;
+    }
+    if !authorized(&req, token) {
+        return respond(&mut stream, "401 Unauthorized", &error_body("Missing or wrong token")).await;
+    }
+
+    if req.path == "/api/ws" {
+        return websocket(app, req, stream, shutdown).await;
+    }
+
+    match to_command(&req) {
+        Ok(command) => {
+            let response = control::execute(&app, &command).await;
+            let status = if response.ok { "200 OK" } else { "400 Bad Request" };
+            let body = serde_json::to_value(&response).map_err(|e| e.to_string())?;
+            respond(&mut stream, status, &body).await
+        }
+        Err((status, message)) => respond(&mut stream, status, &error_body(&message)).await,
+    }
+}
+
+/// Reply to `{ "id", "command", "args" }` messages with `{ "type": "response", "id", ... }`
+async fn ws_command(app: &tauri::AppHandle, text: &str) -> String {
+    let msg: Value = serde_json::from_str(text).unwrap_or(Value::Null);
+    let response = match serde_json::from_value::<Request>(msg.clone()) {
+        Ok(req) => control::execute(app, &req).await,
+        Err(e) => Response { ok: false, data: Value::Null, error: Some(format!("Bad request: {}", e)) },
+    };
+    json!({
+        "type": "response",
+        "id": msg["id"],
+        "ok": response.ok,
+        "data": response.data,
+        "error": response.error,
+    })
+    .to_string()
+}
+
+async fn websocket(app: tauri::AppHandle, req: HttpRequest, mut stream: TcpStream, mut shutdown: watch::Receiver<()>) -> Result<(), String> {
+    let Some(key) = req.headers.get("sec-websocket-key") else {
+        return respond(&mut stream, "400 Bad Request", &error_body("Expected a WebSocket upgrade")).await;
+    };
+    let head = format!(
+        "HTTP/1.1 101 Switching Protocols\r\nUpgrade: websocket\r\nConnection: Upgrade\r\nSec-WebSocket-Accept: {}\r\n\r\n",
+        derive_accept_key(key.as_bytes())
+    );
+    stream.write_all(head.as_bytes()).await.map_err(|e| e.to_string())?;
+    let mut ws = WebSocketStream::from_raw_socket(stream, Role::Server, None).await;
+
+    let mut events = subscribe(&app);
+
+    // Start every client off with a full snapshot
+    for command in ["now-playing", "queue"] {
+        let response = control::execute(&app, &Request { command: command.to_string(), args: Vec::new() }).await;
+        let msg = json!({ "type": command, "data": response.data }).to_string();
+        ws.send(Message::Text(msg.into())).await.map_err(|e| e.to_string())?;
+    }
+
+    loop {
+        tokio::select! {
+            incoming = ws.next() => match incoming {
+                Some(Ok(Message::Text(text))) => {
+                    let reply = ws_command(&app, &text).await;
+                    ws.send(Message::Text(reply.into())).await.map_err(|e| e.to_string())?;
+                }
+                Some(Ok(Message::Close(_))) | None => return Ok(()),
+                Some(Ok(_)) => {}
+                Some(Err(e)) => return Err(e.to_string()),
+            },
+            event = events.recv() => match event {
+                Ok(msg) => ws.send(Message::Text(msg.into())).await.map_err(|e| e.to_string())?,
+                // A slow client just misses intermediate states
+                Err(broadcast::error::RecvError::Lagged(_)) => {}
+                Err(broadcast::error::RecvError::Closed) => return Ok(()),
+            },
+            _ = shutdown.changed() => {
+                let _ = ws.close(None).await;
+                return Ok(());
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Send `raw` over a loopback connection and parse it the way the server does
+    async fn parse(raw: &str) -> Result<HttpRequest, String> {
+        let listener = TcpListener::bind(("127.0.0.1", 0)).await.unwrap();
+        let mut client = TcpStream::connect(listener.local_addr().unwrap()).await.unwrap();
+        let raw = raw.to_string();
+        tokio::spawn(async move {
+            let _ = client.write_all(raw.as_bytes()).await;
+        });
+        let (mut stream, _) = listener.accept().await.unwrap();
+        read_request(&mut stream).await
+    }
+
+    async fn get(target: &str, headers: &str) -> HttpRequest {
+        parse(&format!("GET {} HTTP/1.1\r\nHost: localhost\r\n{}\r\n", target, headers)).await.unwrap()
+    }
+
+    async fn post(target: &str, body: &str) -> HttpRequest {
+        let raw = format!("POST {} HTTP/1.1\r\nContent-Length: {}\r\n\r\n{}", target, body.len(), body);
+        parse(&raw).await.unwrap()
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn parses_request_line_headers_query_and_body() {
+        let body = r#"{"args":["1:30"]}"#;
+        let raw = format!(
+            "POST /api/seek?token=a%20b&x=1 HTTP/1.1\r\nHost: localhost\r\nX-Custom:  Spaced \r\nContent-Length: {}\r\n\r\n{}",
+            body.len(),
+            body
+        );
+        let req = parse(&raw).await.unwrap();
+        assert_eq!(req.method, "POST");
+        assert_eq!(req.path, "/api/seek");
+        assert_eq!(req.query.get("token").map(String::as_str), Some("a b"));
+        assert_eq!(req.query.get("x").map(String::as_str), Some("1"));
+        assert_eq!(req.headers.get("x-custom").map(String::as_str), Some("Spaced"));
+        assert_eq!(req.body, body.as_bytes());
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn rejects_oversized_and_truncated_requests() {
+        let big_head = format!("GET / HTTP/1.1\r\nX-Filler: {}\r\n", "a".repeat(MAX_HEAD + 1));
+        assert_eq!(parse(&big_head).await.err().unwrap(), "Request head too large");
+        let big_body = format!("POST /api/play HTTP/1.1\r\nContent-Length: {}\r\n\r\n", MAX_BODY + 1);
+        assert_eq!(parse(&big_body).await.err().unwrap(), "Request body too large");
+        assert_eq!(parse("GET / HTTP/1.1\r\n").await.err().unwrap(), "Connection closed");
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn accepts_bearer_or_query_token() {
+        assert!(authorized(&get("/api/queue", "Authorization: Bearer secret\r\n").await, "secret"));
+        assert!(authorized(&get("/api/queue?token=secret", "").await, "secret"));
+        assert!(!authorized(&get("/api/queue", "Authorization: Bearer wrong!\r\n").await, "secret"));
+        assert!(!authorized(&get("/api/queue", "Authorization: Basic secret\r\n").await, "secret"));
+        assert!(!authorized(&get("/api/queue?token=secre", "").await, "secret"));
+        assert!(!authorized(&get("/api/queue", "").await, "secret"));
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn maps_endpoints_to_commands() {
+        let command =
+            |req: &HttpRequest| to_command(req).map(|r| (r.command, r.args)).map_err(|(status, _)| status);
+
+        assert_eq!(command(&get("/api/now-playing", "").await), Ok(("now-playing".to_string(), vec![])));
+        assert_eq!(
+            command(&get("/api/search?q=sigur%20ros", "").await),
+            Ok(("search".to_string(), vec!["sigur ros".to_string()]))
+        );
+        assert_eq!(
+            command(&post("/api/seek", r#"{"args":["1:30"]}"#).await),
+            Ok(("seek".to_string(), vec!["1:30".to_string()]))
+        );
+        assert_eq!(command(&post("/api/next", "").await), Ok(("next".to_string(), vec![])));
+
+        assert_eq!(command(&get("/api/next", "").await), Err("405 Method Not Allowed"));
+        assert_eq!(command(&post("/api/seek", "{").await), Err("400 Bad Request"));
+        for path in ["/", "/api/", "/api/a/b", "/other"] {
+            assert_eq!(command(&get(path, "").await), Err("404 Not Found"), "{}", path);
+        }
+    }
+}
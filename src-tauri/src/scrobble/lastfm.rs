@@ -0,0 +1,281 @@
+use async_trait::async_trait;
+use serde_json::Value;
+use std::collections::BTreeMap;
+use tauri::{Manager, State};
+
+use super::provider::{Scrobbler, SubmitError};
+use super::{Listen, ScrobbleState};
+use crate::settings::{self, LastfmSettings, SettingsState};
+
+/// Error codes worth retrying: service offline, temporarily unavailable, rate limited
+const TEMPORARY_ERRORS: [i64; 3] = [11, 16, 29];
+
+/// Last.fm rejects scrobbles older than this
+const MAX_AGE_SECS: i64 = 14 * 24 * 3600;
+
+pub struct Lastfm {
+    api_url: String,
+    api_key: String,
+    api_secret: String,
+    session_key: String,
+}
+
+impl Lastfm {
+    pub fn new(cfg: &LastfmSettings) -> Self {
+        Self {
+            api_url: cfg.api_url.clone(),
+            api_key: cfg.api_key.clone(),
+            api_secret: cfg.api_secret.clone(),
+            session_key: cfg.session_key.clone(),
+        }
+    }
+
+    /// Send `track.scrobble`, leaving out listens too old for Last.fm to accept
+    async fn scrobble(&self, listens: &[Listen]) -> Result<(), SubmitError> {
+        let cutoff = crate::plays::unix_now() - MAX_AGE_SECS;
+        let fresh: Vec<&Listen> = listens.iter().filter(|l| l.listened_at > cutoff).collect();
+        if fresh.is_empty() {
+            return Err(SubmitError::Rejected("older than 14 days".to_string()));
+        }
+
+        let mut params = BTreeMap::new();
+        params.insert("sk".to_string(), self.session_key.clone());
+        for (i, listen) in fresh.iter().enumerate() {
+            let suffix = format!("[{}]", i);
+            track_params(listen, &suffix, &mut params);
+            params.insert(format!("timestamp{}", suffix), listen.listened_at.to_string());
+        }
+
+        match call(&self.api_url, &self.api_key, &self.api_secret, "track.scrobble", params).await {
+            Ok(_) => Ok(()),
+            Err(CallError::Api { code, message }) if !TEMPORARY_ERRORS.contains(&code) => {
+                // Bad session or key: keep the listens until the user signs in again
+                if code == 9 || code == 10 || code == 26 {
+                    Err(SubmitError::Retry(format!("error {}: {}", code, message)))
+                } else {
+                    Err(SubmitError::Rejected(format!("error {}: {}", code, message)))
+                }
+            }
+            Err(e) => Err(SubmitError::Retry(e.to_string())),
+        }
+    }
+}
+
+/// `api_sig`: md5 of every parameter as `keyvalue`, sorted by key, followed by the shared secret
+fn sign(params: &BTreeMap<String, String>, secret: &str) -> String {
+    let mut raw: String = params.iter().map(|(k, v)| format!("{}{}", k, v)).collect();
+    raw.push_str(secret);
+    format!("{:x}", md5::compute(raw.as_bytes()))
+}
+
+enum CallError {
+    Network(String),
+    Api { code: i64, message: String },
+}
+
+impl std::fmt::Display for CallError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CallError::Network(e) => write!(f, "{}", e),
+            CallError::Api { code, message } => write!(f, "error {}: {}", code, message),
+        }
+    }
+}
+
+/// Make a signed API call. `method`, `api_key` and `api_sig` are added here.
+async fn call(
+    api_url: &str,
+    api_key: &str,
+    secret: &str,
+    method: &str,
+    mut params: BTreeMap<String, String>,
+) -> Result<Value, CallError> {
+    params.insert("method".to_string(), method.to_string());
+    params.insert("api_key".to_string(), api_key.to_string());
+    let sig = sign(&params, secret);
+    params.insert("api_sig".to_string(), sig);
+    // `format` is not part of the signature
+    params.insert("format".to_string(), "json".to_string());
+
+    let resp = reqwest::Client::new()
+        .post(api_url)
+        .header("User-Agent", "GoyMusic/1.0")
+        .form(&params)
+        .send()
+        .await
+        .map_err(|e| CallError::Network(e.to_string()))?;
+    let status = resp.status();
+    let body: Value = resp.json().await.map_err(|e| CallError::Network(format!("HTTP {}: {}", status, e)))?;
+
+    if let Some(code) = body["error"].as_i64() {
+        let message = body["message"].as_str().unwrap_or_default().to_string();
+        return Err(CallError::Api { code, message });
+    }
+    if status.is_server_error() {
+        return Err(CallError::Network(format!("HTTP {}", status)));
+    }
+    Ok(body)
+}
+
+fn track_params(listen: &Listen, suffix: &str, params: &mut BTreeMap<String, String>) {
+    params.insert(format!("artist{}", suffix), listen.artist.clone());
+    params.insert(format!("track{}", suffix), listen.title.clone());
+    if !listen.album.is_empty() {
+        params.insert(format!("album{}", suffix), listen.album.clone());
+    }
+    if listen.duration > 0.0 {
+        params.insert(format!("duration{}", suffix), (listen.duration.round() as u64).to_string());
+    }
+}
+
+#[async_trait]
+impl Scrobbler for Lastfm {
+    fn name(&self) -> &'static str {
+        "lastfm"
+    }
+
+    fn batch_size(&self) -> usize {
+        50
+    }
+
+    async fn now_playing(&self, _app: &tauri::AppHandle, listen: &Listen) -> Result<(), String> {
+        let mut params = BTreeMap::new();
+        params.insert("sk".to_string(), self.session_key.clone());
+        track_params(listen, "", &mut params);
+        call(&self.api_url, &self.api_key, &self.api_secret, "track.updateNowPlaying", params)
+            .await
+            .map(|_| ())
+            .map_err(|e| e.to_string())
+    }
+
+    async fn submit(&self, _app: &tauri::AppHandle, listens: &[Listen]) -> Result<(), SubmitError> {
+        self.scrobble(listens).await
+    }
+}
+
+/// Start desktop authentication. Returns the page the user must open to grant access.
+#[tauri::command]
+pub async fn lastfm_begin_auth(
+    settings: State<'_, SettingsState>,
+    state: State<'_, ScrobbleState>,
+) -> Result<String, String> {
+    let cfg = settings.0.lock().unwrap().scrobble.lastfm.clone();
+    if cfg.api_key.is_empty() || cfg.api_secret.is_empty() {
+        return Err("Set a Last.fm API key and secret first".to_string());
+    }
+
+    let resp = call(&cfg.api_url, &cfg.api_key, &cfg.api_secret, "auth.getToken", BTreeMap::new())
+        .await
+        .map_err(|e| e.to_string())?;
+    let token = resp["token"].as_str().ok_or("No token in response")?.to_string();
+    *state.lastfm_token.lock().unwrap() = Some(token.clone());
+    Ok(format!("{}?api_key={}&token={}", cfg.auth_url, cfg.api_key, token))
+}
+
+/// Exchange the authorized token for a session key and store it. Returns the user name.
+#[tauri::command]
+pub async fn lastfm_finish_auth(app: tauri::AppHandle) -> Result<String, String> {
+    let cfg = app.state::<SettingsState>().0.lock().unwrap().scrobble.lastfm.clone();
+    let token = app
+        .state::<ScrobbleState>()
+        .lastfm_token
+        .lock()
+        .unwrap()
+        .clone()
+        .ok_or("Call lastfm_begin_auth first")?;
+
+    let mut params = BTreeMap::new();
+    params.insert("token".to_string(), token);
+    let resp = call(&cfg.api_url, &cfg.api_key, &cfg.api_secret, "auth.getSession", params)
+        .await
+        .map_err(|e| e.to_string())?;
+    let session = &resp["session"];
+    let key = session["key"].as_str().ok_or("No session key in response")?.to_string();
+    let name = session["name"].as_str().unwrap_or_default().to_string();
+
+    *app.state::<ScrobbleState>().lastfm_token.lock().unwrap() = None;
+    settings::update(&app, |s| {
+        s.scrobble.lastfm.session_key = key;
+        s.scrobble.lastfm.username = name.clone();
+        s.scrobble.lastfm.enabled = true;
+    })
+    .await?;
+    Ok(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scrobble::stub::respond_once;
+
+    fn params(pairs: &[(&str, &str)]) -> BTreeMap<String, String> {
+        pairs.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect()
+    }
+
+    #[test]
+    fn sign_hashes_sorted_params_then_secret() {
+        // Example from the Last.fm authentication docs, inserted out of order
+        let p = params(&[("token", "yyyyyyyy"), ("method", "auth.getSession"), ("api_key", "xxxxxxxx")]);
+        assert_eq!(sign(&p, "ilovecher"), "50b30c3b138fd7e02ae9d9f4b3e84c30");
+    }
+
+    fn lastfm(api_url: String) -> Lastfm {
+        Lastfm { api_url, api_key: "key".to_string(), api_secret: "secret".to_string(), session_key: "sk".to_string() }
+    }
+
+    fn listen(listened_at: i64) -> Listen {
+        Listen {
+            video_id: "abc".to_string(),
+            title: "Song".to_string(),
+            artist: "Band".to_string(),
+            album: String::new(),
+            duration: 0.0,
+            listened_at,
+        }
+    }
+
+    #[tokio::test]
+    async fn scrobble_sends_a_signed_batch() {
+        let (url, form) = respond_once("/2.0/", 200, r#"{"scrobbles":{}}"#).await;
+        let at = crate::plays::unix_now() - 60;
+        assert!(lastfm(url).scrobble(&[listen(at)]).await.is_ok());
+
+        let at = at.to_string();
+        let signed = params(&[
+            ("api_key", "key"),
+            ("artist[0]", "Band"),
+            ("method", "track.scrobble"),
+            ("sk", "sk"),
+            ("timestamp[0]", &at),
+            ("track[0]", "Song"),
+        ]);
+        let form = form.await.unwrap();
+        assert!(form.contains(&format!("api_sig={}", sign(&signed, "secret"))), "{}", form);
+        assert!(form.contains("format=json"));
+    }
+
+    #[tokio::test]
+    async fn scrobble_retries_temporary_and_auth_errors() {
+        for (status, body) in [
+            (200, r#"{"error":11,"message":"Service Offline"}"#),
+            (200, r#"{"error":9,"message":"Invalid session key"}"#),
+            (503, r#"{}"#),
+        ] {
+            let (url, _form) = respond_once("/2.0/", status, body).await;
+            let result = lastfm(url).scrobble(&[listen(crate::plays::unix_now())]).await;
+            assert!(matches!(result, Err(SubmitError::Retry(_))), "{} {}", status, body);
+        }
+    }
+
+    #[tokio::test]
+    async fn scrobble_rejects_bad_listens() {
+        let (url, _form) = respond_once("/2.0/", 200, r#"{"error":6,"message":"Invalid parameters"}"#).await;
+        let result = lastfm(url).scrobble(&[listen(crate::plays::unix_now())]).await;
+        assert!(matches!(result, Err(SubmitError::Rejected(_))));
+
+        // Too old to submit at all, so nothing goes out
+        let stale = crate::plays::unix_now() - MAX_AGE_SECS - 60;
+        let result = lastfm("http://127.0.0.1:9/".to_string()).scrobble(&[listen(stale)]).await;
+        assert!(matches!(result, Err(SubmitError::Rejected(_))));
+    }
+}
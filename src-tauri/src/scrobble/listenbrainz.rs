@@ -0,0 +1,304 @@
+use async_trait::async_trait;
+use serde_json::{json, Map, Value};
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+use tauri::Manager;
+
+use super::provider::{Scrobbler, SubmitError};
+use super::{Listen, ScrobbleState};
+use crate::settings::ListenbrainzSettings;
+
+/// MusicBrainz identifiers for a track, as far as ListenBrainz could match it
+#[derive(Clone, Default)]
+pub struct Mbids {
+    recording: Option<String>,
+    release: Option<String>,
+    artists: Vec<String>,
+}
+
+/// A cached MusicBrainz lookup
+#[derive(Clone)]
+pub enum Lookup {
+    Found(Mbids),
+    /// The request failed at this time; it's retried once [`MISS_TTL`] has passed
+    Failed(Instant),
+}
+
+/// How long a failed lookup is remembered, so an unreachable service doesn't slow every submission
+const MISS_TTL: Duration = Duration::from_secs(600);
+
+/// Longest a single lookup may take
+const LOOKUP_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Cached lookups kept before expired failures, then arbitrary entries, are evicted
+const MBID_CACHE_LIMIT: usize = 5000;
+
+/// Total lookup time per submission. Listens still unresolved after it are sent without MBIDs.
+const BATCH_LOOKUP_BUDGET: Duration = Duration::from_secs(30);
+
+/// Cache `lookup` for `video_id`, making room first if the cache is full
+fn remember(cache: &mut HashMap<String, Lookup>, video_id: &str, lookup: Lookup) {
+    if cache.len() >= MBID_CACHE_LIMIT && !cache.contains_key(video_id) {
+        cache.retain(|_, l| !matches!(l, Lookup::Failed(at) if at.elapsed() >= MISS_TTL));
+        if cache.len() >= MBID_CACHE_LIMIT {
+            if let Some(key) = cache.keys().next().cloned() {
+                cache.remove(&key);
+            }
+        }
+    }
+    cache.insert(video_id.to_string(), lookup);
+}
+
+/// One entry of a submission's `payload` array
+fn build_payload(listen: &Listen, mbids: Mbids, with_timestamp: bool) -> Value {
+    let mut info = Map::new();
+    info.insert("media_player".to_string(), json!("GoyMusic"));
+    info.insert("submission_client".to_string(), json!("GoyMusic"));
+    info.insert("music_service".to_string(), json!("music.youtube.com"));
+    info.insert("origin_url".to_string(), json!(format!("https://music.youtube.com/watch?v={}", listen.video_id)));
+    if listen.duration > 0.0 {
+        info.insert("duration_ms".to_string(), json!((listen.duration * 1000.0).round() as u64));
+    }
+    if let Some(id) = mbids.recording {
+        info.insert("recording_mbid".to_string(), json!(id));
+    }
+    if let Some(id) = mbids.release {
+        info.insert("release_mbid".to_string(), json!(id));
+    }
+    if !mbids.artists.is_empty() {
+        info.insert("artist_mbids".to_string(), json!(mbids.artists));
+    }
+
+    let mut metadata = json!({
+        "artist_name": listen.artist,
+        "track_name": listen.title,
+        "additional_info": info,
+    });
+    if !listen.album.is_empty() {
+        metadata["release_name"] = json!(listen.album);
+    }
+
+    let mut payload = json!({ "track_metadata": metadata });
+    if with_timestamp {
+        payload["listened_at"] = json!(listen.listened_at);
+    }
+    payload
+}
+
+pub struct Listenbrainz {
+    api_url: String,
+    token: String,
+}
+
+impl Listenbrainz {
+    pub fn new(cfg: &ListenbrainzSettings) -> Self {
+        Self { api_url: cfg.api_url.trim_end_matches('/').to_string(), token: cfg.token.clone() }
+    }
+
+    /// Look the track up by name, unless `deadline` has passed. Results are cached per video,
+    /// failures only for [`MISS_TTL`].
+    async fn mbids(&self, app: &tauri::AppHandle, listen: &Listen, deadline: Instant) -> Mbids {
+        let state = app.state::<ScrobbleState>();
+        let cached = state.mbid_cache.lock().unwrap().get(&listen.video_id).cloned();
+        match cached {
+            Some(Lookup::Found(mbids)) => return mbids,
+            Some(Lookup::Failed(at)) if at.elapsed() < MISS_TTL => return Mbids::default(),
+            _ => {}
+        }
+        let timeout = deadline.saturating_duration_since(Instant::now()).min(LOOKUP_TIMEOUT);
+        if timeout.is_zero() {
+            return Mbids::default();
+        }
+
+        let url = format!("{}/1/metadata/lookup/", self.api_url);
+        let resp = reqwest::Client::new()
+            .get(url)
+            .query(&[("artist_name", listen.artist.as_str()), ("recording_name", listen.title.as_str())])
+            .timeout(timeout)
+            .send()
+            .await;
+        let body: Value = match resp {
+            Ok(resp) if resp.status().is_success() => resp.json().await.unwrap_or_default(),
+            _ => {
+                remember(&mut state.mbid_cache.lock().unwrap(), &listen.video_id, Lookup::Failed(Instant::now()));
+                return Mbids::default();
+            }
+        };
+
+        let string = |key: &str| body[key].as_str().filter(|s| !s.is_empty()).map(str::to_string);
+        let mbids = Mbids {
+            recording: string("recording_mbid"),
+            release: string("release_mbid"),
+            artists: body["artist_mbids"]
+                .as_array()
+                .map(|ids| ids.iter().filter_map(|id| id.as_str().map(str::to_string)).collect())
+                .unwrap_or_default(),
+        };
+        remember(&mut state.mbid_cache.lock().unwrap(), &listen.video_id, Lookup::Found(mbids.clone()));
+        mbids
+    }
+
+    async fn payload(&self, app: &tauri::AppHandle, listen: &Listen, with_timestamp: bool, deadline: Instant) -> Value {
+        let mbids = self.mbids(app, listen, deadline).await;
+        build_payload(listen, mbids, with_timestamp)
+    }
+ = Map::new();
+    async fn post(&self, listen_type: &str, payload: Vec<Value>) -> Result<(), SubmitError> {
+        let resp = reqwest::Client::new()
+            .post(format!("{}/1/submit-listens", self.api_url))
+            .header("Authorization", format!("Token {}", self.token))
+            .timeout(Duration::from_secs(30))
+            .json(&json!({ "listen_type": listen_type, "payload": payload }))
+            .send()
+            .await
+            .map_err(|e| SubmitError::Retry(e.to_string()))?;
+
+        let status = resp.status();
+        if status.is_success() {
+            return Ok(());
+        }
+        let body: Value = resp.json().await.unwrap_or_default();
+        let message = format!("HTTP {}: {}", status, body["error"].as_str().unwrap_or_default());
+        // Only a malformed payload is final; a bad token (401) gets fixed in settings, so keep the listens
+        if status.as_u16() == 400 {
+            Err(SubmitError::Rejected(message))
+        } else {
+            Err(SubmitError::Retry(message))
+        }
+    }
+}
+
+#[async_trait]
+impl Scrobbler for Listenbrainz {
+    fn name(&self) -> &'static str {
+        "listenbrainz"
+    }
+
+    fn batch_size(&self) -> usize {
+        100
+    }
+
+    async fn now_playing(&self, app: &tauri::AppHandle, listen: &Listen) -> Result<(), String> {
+        let payload = self.payload(app, listen, false, Instant::now() + LOOKUP_TIMEOUT).await;
+        self.post("playing_now", vec![payload]).await.map_err(|e| match e {
+            SubmitError::Retry(e) | SubmitError::Rejected(e) => e,
+        })
+    }
+
+    async fn submit(&self, app: &tauri::AppHandle, listens: &[Listen]) -> Result<(), SubmitError> {
+        let deadline = Instant::now() + BATCH_LOOKUP_BUDGET;
+        let mut payload = Vec::with_capacity(listens.len());
+        for listen in listens {
+            payload.push(self.payload(app, listen, true, deadline).await);
+        }
+        // "single" is meant for a listen that just happened, "import" for a backlog
+        let listen_type = if listens.len() == 1 { "single" } else { "import" };
+        self.post(listen_type, payload).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scrobble::stub::respond_once;
+
+    fn listen() -> Listen {
+        Listen {
+            video_id: "abc".to_string(),
+            title: "Song".to_string(),
+            artist: "Band".to_string(),
+            album: "Record".to_string(),
+            duration: 201.4,
+            listened_at: 1_700_000_000,
+        }
+    }
+
+    #[test]
+    fn payload_carries_metadata_and_known_mbids() {
+        let mbids = Mbids {
+            recording: Some("rec".to_string()),
+            release: None,
+            artists: vec!["a1".to_string(), "a2".to_string()],
+        };
+        let payload = build_payload(&listen(), mbids, true);
+        assert_eq!(payload["listened_at"], 1_700_000_000);
+        let metadata = &payload["track_metadata"];
+        assert_eq!((metadata["artist_name"].as_str(), metadata["track_name"].as_str()), (Some("Band"), Some("Song")));
+        assert_eq!(metadata["release_name"], "Record");
+        let info = &metadata["additional_info"];
+        assert_eq!(info["origin_url"], "https://music.youtube.com/watch?v=abc");
+        assert_eq!(info["duration_ms"], 201_400);
+        assert_eq!(info["recording_mbid"], "rec");
+        assert_eq!(info["artist_mbids"], json!(["a1", "a2"]));
+        assert!(info.get("release_mbid").is_none());
+    }
+
+    #[test]
+    fn payload_leaves_out_unknowns() {
+        let mut l = listen();
+        l.album.clear();
+        l.duration = 0.0;
+        let payload = build_payload(&l, Mbids::default(), false);
+        assert!(payload.get("listened_at").is_none());
+        let metadata = &payload["track_metadata"];
+        assert!(metadata.get("release_name").is_none());
+        let info = metadata["additional_info"].as_object().unwrap();
+        for key in ["duration_ms", "recording_mbid", "release_mbid", "artist_mbids"] {
+            assert!(!info.contains_key(key), "{}", key);
+        }
+    }
+
+    #[test]
+    fn full_cache_evicts_expired_failures_first() {
+        let expired = Instant::now().checked_sub(MISS_TTL + Duration::from_secs(1)).unwrap();
+        let mut cache = HashMap::new();
+        for i in 0..MBID_CACHE_LIMIT {
+            let lookup = if i % 2 == 0 { Lookup::Failed(expired) } else { Lookup::Found(Mbids::default()) };
+            cache.insert(i.to_string(), lookup);
+        }
+        remember(&mut cache, "new", Lookup::Failed(Instant::now()));
+        assert_eq!(cache.len(), MBID_CACHE_LIMIT / 2 + 1);
+        assert!(cache.contains_key("new") && cache.contains_key("1") && !cache.contains_key("0"));
+    }
+
+    #[test]
+    fn full_cache_stays_at_its_limit() {
+        let mut cache: HashMap<String, Lookup> =
+            (0..MBID_CACHE_LIMIT).map(|i| (i.to_string(), Lookup::Found(Mbids::default()))).collect();
+        remember(&mut cache, "1", Lookup::Failed(Instant::now()));
+        assert_eq!(cache.len(), MBID_CACHE_LIMIT);
+        remember(&mut cache, "new", Lookup::Failed(Instant::now()));
+        assert_eq!(cache.len(), MBID_CACHE_LIMIT);
+        assert!(cache.contains_key("new"));
+    }
+
+    fn listenbrainz(api_url: String) -> Listenbrainz {
+        Listenbrainz { api_url, token: "token".to_string() }
+    }
+
+    #[tokio::test]
+    async fn post_sends_the_listen_type_and_payload() {
+        let (url, body) = respond_once("", 200, r#"{"status":"ok"}"#).await;
+        let payload = vec![build_payload(&listen(), Mbids::default(), true)];
+        assert!(listenbrainz(url).post("single", payload.clone()).await.is_ok());
+        let sent: Value = serde_json::from_str(&body.await.unwrap()).unwrap();
+        assert_eq!(sent, json!({ "listen_type": "single", "payload": payload }));
+    }
+
+    #[tokio::test]
+    async fn post_rejects_only_bad_requests() {
+        let (url, _body) = respond_once("", 400, r#"{"code":400,"error":"Invalid listen"}"#).await;
+        match listenbrainz(url).post("single", Vec::new()).await {
+            Err(SubmitError::Rejected(message)) => assert!(message.contains("Invalid listen"), "{}", message),
+            _ => panic!("expected a rejection"),
+        }
+
+        for status in [401, 429, 500, 503] {
+            let (url, _body) = respond_once("", status, r#"{"error":"nope"}"#).await;
+            let result = listenbrainz(url).post("single", Vec::new()).await;
+            assert!(matches!(result, Err(SubmitError::Retry(_))), "{}", status);
+        }
+        let result = listenbrainz("http://127.0.0.1:9".to_string()).post("single", Vec::new()).await;
+        assert!(matches!(result, Err(SubmitError::Retry(_))));
+    }
+}
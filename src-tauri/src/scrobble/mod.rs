@@ -0,0 +1,212 @@
+mod lastfm;
+mod listenbrainz;
+mod provider;
+#[cfg(test)]
+mod stub;
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::Manager;
+
+use crate::plays::Play;
+use crate::settings::SettingsState;
+use listenbrainz::Lookup;
+use provider::{build, Scrobbler, SubmitError};
+
+pub use lastfm::{lastfm_begin_auth, lastfm_finish_auth};
+
+/// How often queued listens are retried while offline
+const RETRY_INTERVAL: Duration = Duration::from_secs(300);
+
+/// A finished listen waiting to be submitted
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Listen {
+    pub video_id: String,
+    pub title: String,
+    pub artist: String,
+    pub album: String,
+    /// Track length in seconds, 0 if unknown
+    pub duration: f64,
+    /// Unix seconds when playback started
+    pub listened_at: i64,
+}
+
+impl Listen {
+    fn from_play(play: &Play) -> Self {
+        Self {
+            video_id: play.track.video_id.clone(),
+            title: play.track.title.clone(),
+            artist: play.track.artist.clone(),
+            album: play.track.album.clone(),
+            duration: play.track.duration,
+            listened_at: play.started_at,
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct ScrobbleState {
+    /// Serializes access to the queue files
+    queue_lock: Mutex<()>,
+    /// Keeps a retry from overlapping with a submission triggered by a new listen
+    flush_lock: tokio::sync::Mutex<()>,
+    /// Last.fm request token between `lastfm_begin_auth` and `lastfm_finish_auth`
+    lastfm_token: Mutex<Option<String>>,
+    /// MusicBrainz lookups by video id
+    mbid_cache: Mutex<HashMap<String, Lookup>>,
+}
+
+/// Last.fm's rule: tracks over 30 seconds, played for half their length or 4 minutes
+fn counts_as_listen(play: &Play) -> bool {
+    play.track.duration > 30.0 && play.listened >= (play.track.duration / 2.0).min(240.0)
+}
+
+fn queue_path(app: &tauri::AppHandle, service: &str) -> Result<PathBuf, String> {
+    let dir = app.path().app_data_dir().map_err(|e| e.to_string())?;
+    Ok(dir.join("scrobbles").join(format!("{}.json", service)))
+}
+
+fn load_queue(app: &tauri::AppHandle, service: &str) -> Vec<Listen> {
+    queue_path(app, service)
+        .ok()
+        .and_then(|p| std::fs::read_to_string(p).ok())
+        .and_then(|text| serde_json::from_str(&text).ok())
+        .unwrap_or_default()
+}
+
+fn save_queue(app: &tauri::AppHandle, service: &str, listens: &[Listen]) -> Result<(), String> {
+    let path = queue_path(app, service)?;
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+    }
+    let text = serde_json::to_string(listens).map_err(|e| e.to_string())?;
+    std::fs::write(&path, text).map_err(|e| e.to_string())
+}
+
+/// Load, modify and save a service's queue under the queue lock
+fn with_queue(app: &tauri::AppHandle, service: &str, f: impl FnOnce(&mut Vec<Listen>)) {
+    let state = app.state::<ScrobbleState>();
+    let _guard = state.queue_lock.lock().unwrap();
+    let mut listens = load_queue(app, service);
+    f(&mut listens);
+    if let Err(e) = save_queue(app, service, &listens) {
+        eprintln!("Failed to save {} queue: {}", service, e);
+    }
+}
+
+/// Submit a service's queued listens in batches until it's empty or the service is unreachable
+async fn flush(app: &tauri::AppHandle, scrobbler: &dyn Scrobbler) {
+    let service = scrobbler.name();
+    loop {
+        let batch: Vec<Listen> = {
+            let state = app.state::<ScrobbleState>();
+            let _guard = state.queue_lock.lock().unwrap();
+            load_queue(app, service).into_iter().take(scrobbler.batch_size()).collect()
+        };
+        if batch.is_empty() {
+            return;
+        }
+
+        match scrobbler.submit(app, &batch).await {
+            Ok(()) => {}
+            Err(SubmitError::Rejected(e)) => eprintln!("{} rejected {} listens: {}", service, batch.len(), e),
+            Err(SubmitError::Retry(e)) => {
+                eprintln!("{} unavailable, keeping {} listens queued: {}", service, batch.len(), e);
+                return;
+            }
+        }
+        // New listens are only ever appended, so the batch is still at the front
+        with_queue(app, service, |listens| {
+            listens.drain(..batch.len().min(listens.len()));
+        });
+    }
+}
+
+async fn flush_all(app: &tauri::AppHandle) {
+    let cfg = app.state::<SettingsState>().0.lock().unwrap().scrobble.clone();
+    let state = app.state::<ScrobbleState>();
+    let _flushing = state.flush_lock.lock().await;
+    for scrobbler in build(&cfg) {
+        flush(app, scrobbler.as_ref()).await;
+    }
+}
+
+/// Announce the new track as "now playing"
+pub fn play_started(app: &tauri::AppHandle, play: &Play) {
+    let cfg = app.state::<SettingsState>().0.lock().unwrap().scrobble.clone();
+    let listen = Listen::from_play(play);
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        for scrobbler in build(&cfg) {
+            if let Err(e) = scrobbler.now_playing(&app, &listen).await {
+                eprintln!("{} now playing failed: {}", scrobbler.name(), e);
+            }
+        }
+    });
+}
+
+/// Queue a finished play if it qualifies, then try to submit
+pub fn play_finished(app: &tauri::AppHandle, play: &Play) {
+    if !counts_as_listen(play) {
+        return;
+    }
+    let cfg = app.state::<SettingsState>().0.lock().unwrap().scrobble.clone();
+    let scrobblers = build(&cfg);
+    if scrobblers.is_empty() {
+        return;
+    }
+
+    let listen = Listen::from_play(play);
+    for scrobbler in &scrobblers {
+        with_queue(app, scrobbler.name(), |listens| listens.push(listen.clone()));
+    }
+
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move { flush_all(&app).await });
+}
+
+/// Periodically retry listens queued while offline
+pub fn spawn_retry_loop(app: tauri::AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        loop {
+            flush_all(&app).await;
+            tokio::time::sleep(RETRY_INTERVAL).await;
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::now_playing::Track;
+
+    fn play(duration: f64, listened: f64) -> Play {
+        let track = Track {
+            video_id: "abc".to_string(),
+            title: "Song".to_string(),
+            artist: "Band".to_string(),
+            album: String::new(),
+            thumb_url: String::new(),
+            duration,
+        };
+        Play { track, started_at: 0, listened, position: listened }
+    }
+
+    #[test]
+    fn listens_need_half_the_track_or_four_minutes() {
+        assert!(counts_as_listen(&play(200.0, 100.0)));
+        assert!(!counts_as_listen(&play(200.0, 99.0)));
+        // Long tracks count after four minutes
+        assert!(counts_as_listen(&play(3600.0, 240.0)));
+        assert!(!counts_as_listen(&play(3600.0, 239.0)));
+    }
+
+    #[test]
+    fn short_or_unknown_tracks_never_count() {
+        assert!(!counts_as_listen(&play(30.0, 30.0)));
+        assert!(!counts_as_listen(&play(0.0, 500.0)));
+    }
+}
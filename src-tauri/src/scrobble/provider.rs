@@ -0,0 +1,40 @@
+use async_trait::async_trait;
+
+use super::{lastfm::Lastfm, listenbrainz::Listenbrainz, Listen};
+use crate::settings::ScrobbleSettings;
+
+/// Why a submission didn't go through
+pub enum SubmitError {
+    /// Network trouble or a temporary server error; keep the listens and try again later
+    Retry(String),
+    /// The service refused the listens themselves; retrying won't help
+    Rejected(String),
+}
+
+/// A listen tracking service
+#[async_trait]
+pub trait Scrobbler: Send + Sync {
+    /// Also names the service's queue file
+    fn name(&self) -> &'static str;
+
+    /// Most listens accepted in one submission
+    fn batch_size(&self) -> usize;
+
+    async fn now_playing(&self, app: &tauri::AppHandle, listen: &Listen) -> Result<(), String>;
+
+    async fn submit(&self, app: &tauri::AppHandle, listens: &[Listen]) -> Result<(), SubmitError>;
+}
+
+/// Instantiate the services that are enabled and signed in
+pub fn build(cfg: &ScrobbleSettings) -> Vec<Box<dyn Scrobbler>> {
+    let mut scrobblers: Vec<Box<dyn Scrobbler>> = Vec::new();
+    let lastfm = &cfg.lastfm;
+    if lastfm.enabled && !lastfm.session_key.is_empty() && !lastfm.api_key.is_empty() {
+        scrobblers.push(Box::new(Lastfm::new(lastfm)));
+    }
+    let listenbrainz = &cfg.listenbrainz;
+    if listenbrainz.enabled && !listenbrainz.token.is_empty() {
+        scrobblers.push(Box::new(Listenbrainz::new(listenbrainz)));
+    }
+    scrobblers
+}
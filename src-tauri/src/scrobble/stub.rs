@@ -0,0 +1,42 @@
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+use tokio::sync::oneshot;
+
+/// Answer one HTTP request with `status` and a JSON `body`. Returns the URL of `path` on the stub
+/// and the request body it received.
+pub async fn respond_once(path: &str, status: u16, body: &'static str) -> (String, oneshot::Receiver<String>) {
+    let listener = TcpListener::bind(("127.0.0.1", 0)).await.unwrap();
+    let url = format!("http://{}{}", listener.local_addr().unwrap(), path);
+    let (tx, rx) = oneshot::channel();
+    tokio::spawn(async move {
+        let (mut stream, _) = listener.accept().await.unwrap();
+        let mut buf = Vec::new();
+        let mut chunk = [0u8; 4096];
+        let received = loop {
+            let n = stream.read(&mut chunk).await.unwrap();
+            buf.extend_from_slice(&chunk[..n]);
+            let text = String::from_utf8_lossy(&buf).to_string();
+            let Some((head, rest)) = text.split_once("\r\n\r\n") else {
+                assert!(n > 0, "connection closed mid-request");
+                continue;
+            };
+            let length = head
+                .lines()
+                .find_map(|l| l.to_ascii_lowercase().strip_prefix("content-length:").map(|v| v.trim().to_string()))
+                .and_then(|v| v.parse::<usize>().ok())
+                .unwrap_or(0);
+            if n == 0 || rest.len() >= length {
+                break rest.to_string();
+            }
+        };
+        let reply = format!(
+            "HTTP/1.1 {} Stub\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+            status,
+            body.len(),
+            body
+        );
+        stream.write_all(reply.as_bytes()).await.unwrap();
+        let _ = tx.send(received);
+    });
+    (url, rx)
+}
@@ -0,0 +1,743 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use tauri::{Emitter, Manager, State};
+
+use crate::discord;
+
+/// Current schema version, bumped whenever a migration is added
+pub const SETTINGS_VERSION: u32 = 1;
+
+/// `MIGRATIONS[n]` upgrades a version `n` document to version `n + 1`
+const MIGRATIONS: [fn(&mut Value); SETTINGS_VERSION as usize] = [
+    // 0 → 1: files written before versioning already match the v1 layout
+    |_| {},
+];
+
+pub struct SettingsState(pub Mutex<Settings>);
+
+/// Serializes settings changes, which may wait on server restarts
+#[derive(Default)]
+pub struct SettingsWriter(tokio::sync::Mutex<()>);
+
+/// User-facing settings, persisted as JSON in the app config dir
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    pub version: u32,
+    pub discord: DiscordSettings,
+    pub lyrics: LyricsSettings,
+    pub window: WindowSettings,
+    pub shortcuts: ShortcutSettings,
+    pub notifications: NotificationSettings,
+    pub bridge: BridgeSettings,
+    pub remote: RemoteSettings,
+    pub mpd: MpdSettings,
+    pub scrobble: ScrobbleSettings,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            version: SETTINGS_VERSION,
+            discord: DiscordSettings::default(),
+            lyrics: LyricsSettings::default(),
+            window: WindowSettings::default(),
+            shortcuts: ShortcutSettings::default(),
+            notifications: NotificationSettings::default(),
+            bridge: BridgeSettings::default(),
+            remote: RemoteSettings::default(),
+            mpd: MpdSettings::default(),
+            scrobble: ScrobbleSettings::default(),
+        }
+    }
+}
+
+/// Main window behaviour
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WindowSettings {
+    /// Closing the main window hides it to the tray instead of quitting
+    pub close_to_tray: bool,
+    /// Start hidden in the tray
+    pub start_minimized: bool,
+    pub always_on_top: bool,
+}
+
+impl Default for WindowSettings {
+    fn default() -> Self {
+        Self { close_to_tray: true, start_minimized: false, always_on_top: false }
+    }
+}
+
+/// Timeouts for requests relayed through the hidden YouTube Music webview
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BridgeSettings {
+    /// Innertube API requests
+    pub request_timeout_secs: u64,
+    /// Time given to a page to load before a scraper runs
+    pub page_load_wait_ms: u64,
+    /// Scraper results, counted after the page load wait
+    pub scrape_timeout_secs: u64,
+    /// Playback state polls. These are frequent, so keep this short.
+    pub state_timeout_ms: u64,
+}
+
+impl Default for BridgeSettings {
+    fn default() -> Self {
+        Self {
+            request_timeout_secs: 15,
+            page_load_wait_ms: 4000,
+            scrape_timeout_secs: 10,
+            state_timeout_ms: 2000,
+        }
+    }
+}
+
+/// Discord Rich Presence options
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DiscordSettings {
+    /// Master switch for Rich Presence
+    pub enabled: bool,
+    /// Discord application ID used for the IPC handshake
+    pub client_id: String,
+    /// First line while playing. Supports `{title}`, `{artist}`, `{album}`
+    pub details_template: String,
+    /// Second line. Supports `{title}`, `{artist}`, `{album}`
+    pub state_template: String,
+    /// First line while paused
+    pub paused_template: String,
+    pub show_listen_button: bool,
+    pub show_download_button: bool,
+    /// Clear the presence instead of showing a "Paused" line
+    pub hide_when_paused: bool,
+    /// Never show anything on Discord
+    pub incognito: bool,
+    /// Playlist IDs whose tracks never show up on Discord
+    pub hidden_playlists: Vec<String>,
+    /// Small image asset key shown while playing
+    pub playing_image: String,
+    /// Small image asset key shown while paused
+    pub paused_image: String,
+    /// Seconds of pause/inactivity before the presence is cleared, 0 to never clear
+    pub idle_timeout_secs: u64,
+}
+
+impl Default for DiscordSettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            client_id: "1194717480627740753".to_string(),
+            details_template: "{title}".to_string(),
+            state_template: "{artist}".to_string(),
+            paused_template: "Paused: {title}".to_string(),
+            show_listen_button: true,
+            show_download_button: true,
+            hide_when_paused: false,
+            incognito: false,
+            hidden_playlists: Vec::new(),
+            playing_image: "playing".to_string(),
+            paused_image: "paused".to_string(),
+            idle_timeout_secs: 300,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LyricsProviderKind {
+    /// `.lrc` files in `local_folder`
+    Local,
+    Lrclib,
+    /// The "Lyrics" tab of YouTube Music itself
+    YoutubeMusic,
+}
+
+/// Lyrics lookup options
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LyricsSettings {
+    /// How long a "no lyrics found" result is trusted before asking again
+    pub negative_cache_hours: u64,
+    /// Providers to try, highest priority first
+    pub providers: Vec<LyricsProviderKind>,
+    /// Folder searched by the local provider
+    pub local_folder: Option<String>,
+    /// LRCLIB API root, for self-hosted mirrors
+    pub lrclib_url: String,
+    pub romanize: RomanizeSettings,
+}
+
+/// Which scripts get a transliterated secondary line
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RomanizeSettings {
+    /// Kana to romaji. Kanji are left as-is.
+    pub japanese: bool,
+    /// Hangul to Revised Romanization
+    pub korean: bool,
+    pub cyrillic: bool,
+}
+
+impl Default for RomanizeSettings {
+    fn default() -> Self {
+        Self { japanese: true, korean: true, cyrillic: true }
+    }
+}
+
+impl Default for LyricsSettings {
+    fn default() -> Self {
+        Self {
+            negative_cache_hours: 72,
+            providers: vec![
+                LyricsProviderKind::Local,
+                LyricsProviderKind::Lrclib,
+                LyricsProviderKind::YoutubeMusic,
+            ],
+            local_folder: None,
+            lrclib_url: "https://lrclib.net/api".to_string(),
+            romanize: RomanizeSettings::default(),
+        }
+    }
+}
+
+/// HTTP/WebSocket remote control for phones and other machines
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RemoteSettings {
+    pub enabled: bool,
+    /// `127.0.0.1` for this machine only, `0.0.0.0` to accept LAN clients
+    pub bind_address: String,
+    pub port: u16,
+    /// Clients must send this as `Authorization: Bearer <token>` or `?token=`.
+    /// Generated when the server is first enabled.
+    pub token: String,
+}
+
+impl Default for RemoteSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            bind_address: "127.0.0.1".to_string(),
+            port: 9863,
+            token: String::new(),
+        }
+    }
+}
+
+/// MPD protocol listener for existing MPD clients
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MpdSettings {
+    pub enabled: bool,
+    pub bind_address: String,
+    pub port: u16,
+}
+
+impl Default for MpdSettings {
+    fn default() -> Self {
+        Self { enabled: false, bind_address: "127.0.0.1".to_string(), port: 6600 }
+    }
+}
+
+/// Listen tracking services
+#[derive(Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ScrobbleSettings {
+    pub lastfm: LastfmSettings,
+    pub listenbrainz: ListenbrainzSettings,
+}
+
+/// Last.fm, or any service speaking the same API (e.g. Libre.fm)
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LastfmSettings {
+    pub enabled: bool,
+    /// Endpoint for signed API calls
+    pub api_url: String,
+    /// Page where the user grants access to a request token
+    pub auth_url: String,
+    pub api_key: String,
+    pub api_secret: String,
+    /// Obtained through `lastfm_finish_auth`
+    pub session_key: String,
+    pub username: String,
+}
+
+impl Default for LastfmSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            api_url: "https://ws.audioscrobbler.com/2.0/".to_string(),
+            auth_url: "https://www.last.fm/api/auth/".to_string(),
+            api_key: String::new(),
+            api_secret: String::new(),
+            session_key: String::new(),
+            username: String::new(),
+        }
+    }
+}
+
+/// ListenBrainz, or a self-hosted instance
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ListenbrainzSettings {
+    pub enabled: bool,
+    /// API root, without the `/1/` version segment
+    pub api_url: String,
+    /// User token from the ListenBrainz settings page
+    pub token: String,
+}
+
+impl Default for ListenbrainzSettings {
+    fn default() -> Self {
+        Self { enabled: false, api_url: "https://api.listenbrainz.org".to_string(), token: String::new() }
+    }
+}
+
+/// Native notifications on track change
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct NotificationSettings {
+    pub enabled: bool,
+    /// Attach the album art to the notification
+    pub show_cover: bool,
+    /// Minimum seconds between two notifications, so skipping through a queue doesn't spam
+    pub min_interval_secs: u64,
+}
+
+impl Default for NotificationSettings {
+    fn default() -> Self {
+        Self { enabled: false, show_cover: true, min_interval_secs: 5 }
+    }
+}
+
+/// Global keyboard shortcuts as accelerator strings (e.g. `CmdOrCtrl+Alt+Right`).
+/// An empty string leaves the action unbound.
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ShortcutSettings {
+    pub enabled: bool,
+    pub play_pause: String,
+    pub next: String,
+    pub previous: String,
+    pub volume_up: String,
+    pub volume_down: String,
+    pub like: String,
+    pub dislike: String,
+    pub show_window: String,
+}
+
+impl Default for ShortcutSettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            play_pause: "CmdOrCtrl+Alt+Space".to_string(),
+            next: "CmdOrCtrl+Alt+Right".to_string(),
+            previous: "CmdOrCtrl+Alt+Left".to_string(),
+            volume_up: "CmdOrCtrl+Alt+Up".to_string(),
+            volume_down: "CmdOrCtrl+Alt+Down".to_string(),
+            like: "CmdOrCtrl+Alt+L".to_string(),
+            dislike: "CmdOrCtrl+Alt+D".to_string(),
+            show_window: "CmdOrCtrl+Alt+M".to_string(),
+        }
+    }
+}
+
+fn settings_path(app: &tauri::AppHandle) -> Result<PathBuf, String> {
+    let dir = app.path().app_config_dir().map_err(|e| e.to_string())?;
+    Ok(dir.join("settings.json"))
+}
+
+/// Bring a settings document up to the current schema. Returns the version it started at.
+fn migrate(doc: &mut Value) -> Result<u32, String> {
+    run_migrations(doc, &MIGRATIONS)
+}
+
+/// Apply the `steps` a document is missing; the target version is the number of steps
+fn run_migrations(doc: &mut Value, steps: &[fn(&mut Value)]) -> Result<u32, String> {
+    let Some(obj) = doc.as_object_mut() else {
+        return Err("expected a JSON object".to_string());
+    };
+    let to = steps.len() as u32;
+    let from = obj.get("version").and_then(Value::as_u64).unwrap_or(0) as u32;
+    if from > to {
+        return Err(format!("written by a newer GoyMusic (schema version {})", from));
+    }
+    for step in &steps[from as usize..] {
+        step(doc);
+    }
+    doc["version"] = to.into();
+    Ok(from)
+}
+
+/// Check values that deserialize fine but can't work, reporting every problem at once
+fn validate(s: &Settings) -> Result<(), String> {
+    let mut errors = Vec::new();
+
+    if s.discord.client_id.is_empty() || !s.discord.client_id.chars().all(|c| c.is_ascii_digit()) {
+        errors.push("discord.client_id must be a numeric application ID".to_string());
+    }
+
+    if s.lyrics.providers.is_empty() {
+        errors.push("lyrics.providers must list at least one provider".to_string());
+    }
+    for (i, p) in s.lyrics.providers.iter().enumerate() {
+        if s.lyrics.providers[..i].contains(p) {
+            errors.push("lyrics.providers contains duplicates".to_string());
+            break;
+        }
+    }
+    if !s.lyrics.lrclib_url.starts_with("http://") && !s.lyrics.lrclib_url.starts_with("https://") {
+        errors.push("lyrics.lrclib_url must be an http(s) URL".to_string());
+    }
+
+    if !(1..=120).contains(&s.bridge.request_timeout_secs) {
+        errors.push("bridge.request_timeout_secs must be between 1 and 120".to_string());
+    }
+    if !(1..=120).contains(&s.bridge.scrape_timeout_secs) {
+        errors.push("bridge.scrape_timeout_secs must be between 1 and 120".to_string());
+    }
+    if s.bridge.page_load_wait_ms > 30_000 {
+        errors.push("bridge.page_load_wait_ms must be at most 30000".to_string());
+    }
+    if !(250..=10_000).contains(&s.bridge.state_timeout_ms) {
+        errors.push("bridge.state_timeout_ms must be between 250 and 10000".to_string());
+    }
+
+    if s.remote.bind_address.parse::<std::net::IpAddr>().is_err() {
+        errors.push("remote.bind_address must be an IP address".to_string());
+    }
+    if s.remote.port == 0 {
+        errors.push("remote.port must not be 0".to_string());
+    }
+    if s.remote.enabled && s.remote.token.len() < 16 {
+        errors.push("remote.token must be at least 16 characters".to_string());
+    }
+
+    if s.mpd.bind_address.parse::<std::net::IpAddr>().is_err() {
+        errors.push("mpd.bind_address must be an IP address".to_string());
+    }
+    if s.mpd.port == 0 {
+        errors.push("mpd.port must not be 0".to_string());
+    }
+    if s.remote.enabled && s.mpd.enabled && s.remote.port == s.mpd.port && s.remote.bind_address == s.mpd.bind_address {
+        errors.push("remote.port and mpd.port must differ".to_string());
+    }
+
+    for (name, url) in [("api_url", &s.scrobble.lastfm.api_url), ("auth_url", &s.scrobble.lastfm.auth_url)] {
+        if !url.starts_with("http://") && !url.starts_with("https://") {
+            errors.push(format!("scrobble.lastfm.{} must be an http(s) URL", name));
+        }
+    }
+    let listenbrainz = &s.scrobble.listenbrainz;
+    if !listenbrainz.api_url.starts_with("http://") && !listenbrainz.api_url.starts_with("https://") {
+        errors.push("scrobble.listenbrainz.api_url must be an http(s) URL".to_string());
+    }
+    if listenbrainz.enabled && listenbrainz.token.trim().is_empty() {
+        errors.push("scrobble.listenbrainz.token is required when enabled".to_string());
+    }
+
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors.join("; "))
+    }
+}
+
+/// Fill in values the user never picks themselves. Returns whether anything changed.
+fn fill_generated(s: &mut Settings) -> bool {
+    if s.remote.enabled && s.remote.token.is_empty() {
+        s.remote.token = uuid::Uuid::new_v4().simple().to_string();
+        return true;
+    }
+    false
+}
+
+/// Returns the settings and whether they differ from what's on disk
+fn parse(text: &str) -> Result<(Settings, bool), String> {
+    let mut doc: Value = serde_json::from_str(text).map_err(|e| e.to_string())?;
+    let from = migrate(&mut doc)?;
+    let mut settings: Settings = serde_json::from_value(doc).map_err(|e| e.to_string())?;
+    let generated = fill_generated(&mut settings);
+    validate(&settings)?;
+    Ok((settings, from != SETTINGS_VERSION || generated))
+}
+
+/// Read settings from disk, migrating older files and falling back to defaults if unusable
+pub fn load(app: &tauri::AppHandle) -> Settings {
+    let path = match settings_path(app) {
+        Ok(p) => p,
+        Err(_) => return Settings::default(),
+    };
+    let Ok(text) = std::fs::read_to_string(&path) else {
+        return Settings::default();
+    };
+    match parse(&text) {
+        Ok((settings, changed)) => {
+            if changed {
+                if let Err(e) = save(app, &settings) {
+                    eprintln!("Failed to save updated settings: {}", e);
+                }
+            }
+            settings
+        }
+        Err(e) => {
+            eprintln!("Invalid settings file {}: {}", path.display(), e);
+            // Keep the rejected file so the next save doesn't silently destroy it
+            let _ = std::fs::rename(&path, path.with_extension("json.bak"));
+            Settings::default()
+        }
+    }
+}
+
+/// Apply window behaviours to the main window
+pub fn apply_window(app: &tauri::AppHandle, cfg: &WindowSettings) {
+    if let Some(window) = app.get_webview_window("main") {
+        let _ = window.set_always_on_top(cfg.always_on_top);
+    }
+}
+
+/// Reconfigure running subsystems after a settings change and notify the frontend
+fn on_change(app: &tauri::AppHandle, prev: &Settings, next: &Settings) {
+    if prev.discord != next.discord {
+        discord::reconfigure(app, &next.discord);
+    }
+    if prev.window != next.window {
+        apply_window(app, &next.window);
+    }
+    let _ = app.emit("settings-changed", next);
+}
+
+fn save(app: &tauri::AppHandle, settings: &Settings) -> Result<(), String> {
+    let path = settings_path(app)?;
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+    }
+    let text = serde_json::to_string_pretty(settings).map_err(|e| e.to_string())?;
+    std::fs::write(&path, text).map_err(|e| e.to_string())
+}
+
+/// Write the in-memory settings back to disk, e.g. right before exiting
+pub fn flush(app: &tauri::AppHandle) {
+    let Some(state) = app.try_state::<SettingsState>() else { return };
+    let settings = state.0.lock().unwrap().clone();
+    if let Err(e) = save(app, &settings) {
+        eprintln!("Failed to save settings: {}", e);
+    }
+}
+
+/// Recursively merge `patch` into `target`, replacing non-object values
+fn merge_json(target: &mut Value, patch: Value) {
+    match (target, patch) {
+        (Value::Object(t), Value::Object(p)) => {
+            for (k, v) in p {
+                merge_json(t.entry(k).or_insert(Value::Null), v);
+            }
+        }
+        (t, p) => *t = p,
+    }
+}
+
+#[tauri::command]
+pub fn get_settings(state: State<'_, SettingsState>) -> Settings {
+    state.0.lock().unwrap().clone()
+}
+
+/// Restart the network servers whose settings differ between `from` and `to`
+async fn restart_servers(app: &tauri::AppHandle, from: &Settings, to: &Settings) -> Result<(), String> {
+    if to.remote != from.remote {
+        crate::remote::restart(app, &to.remote).await?;
+    }
+    if to.mpd != from.mpd {
+        crate::mpd::restart(app, &to.mpd).await?;
+    }
+    Ok(())
+}
+
+/// Validate, persist and activate the settings `change` derives from the current ones.
+/// Changes run one at a time; the settings lock itself is never held across a server restart.
+async fn commit(
+    app: &tauri::AppHandle,
+    change: impl FnOnce(&Settings) -> Result<Settings, String> + Send,
+) -> Result<Settings, String> {
+    let writer = app.state::<SettingsWriter>();
+    let _writing = writer.0.lock().await;
+    let current = app.state::<SettingsState>().0.lock().unwrap().clone();
+    let mut updated = change(&current)?;
+
+    updated.version = SETTINGS_VERSION;
+    fill_generated(&mut updated);
+    validate(&updated).map_err(|e| format!("Invalid settings: {}", e))?;
+    // Only checked when it changes: a folder on an unplugged drive shouldn't invalidate the file on load
+    if updated.lyrics.local_folder != current.lyrics.local_folder {
+        if let Some(folder) = updated.lyrics.local_folder.as_deref().filter(|f| !f.is_empty()) {
+            if !Path::new(folder).is_dir() {
+                return Err(format!("Invalid settings: lyrics.local_folder \"{}\" is not a folder", folder));
+            }
+        }
+    }
+
+    #[cfg(desktop)]
+    if updated.shortcuts != current.shortcuts {
+        if let Err(e) = crate::shortcuts::apply(app, &updated.shortcuts) {
+            // Put the previous bindings back so a typo doesn't leave everything unbound
+            let _ = crate::shortcuts::apply(app, &current.shortcuts);
+            return Err(e);
+        }
+    }
+
+    if let Err(e) = restart_servers(app, &current, &updated).await {
+        // Bring the previous servers back so a taken port doesn't leave them off
+        let _ = restart_servers(app, &updated, &current).await;
+        return Err(e);
+    }
+
+    save(app, &updated)?;
+    *app.state::<SettingsState>().0.lock().unwrap() = updated.clone();
+    on_change(app, &current, &updated);
+    Ok(updated)
+}
+
+/// Change settings from Rust, going through the same checks as `update_settings`
+pub async fn update(app: &tauri::AppHandle, f: impl FnOnce(&mut Settings) + Send) -> Result<Settings, String> {
+    commit(app, |current| {
+        let mut updated = current.clone();
+        f(&mut updated);
+        Ok(updated)
+    })
+    .await
+}
+
+/// Apply a partial settings object (e.g. `{ "discord": { "incognito": true } }`) and persist it
+#[tauri::command]
+pub async fn update_settings(app: tauri::AppHandle, patch: Value) -> Result<Settings, String> {
+    commit(&app, |current| {
+        let mut merged = serde_json::to_value(current).map_err(|e| e.to_string())?;
+        merge_json(&mut merged, patch);
+        serde_json::from_value(merged).map_err(|e| format!("Invalid settings: {}", e))
+    })
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn unversioned_files_are_migrated() {
+        let (settings, changed) = parse(r#"{ "discord": { "incognito": true } }"#).unwrap();
+        assert!(changed);
+        assert_eq!(settings.version, SETTINGS_VERSION);
+        assert!(settings.discord.incognito);
+
+        let current = format!(r#"{{ "version": {} }}"#, SETTINGS_VERSION);
+        assert!(!parse(&current).unwrap().1);
+    }
+
+    #[test]
+    fn migrations_run_in_order_from_the_file_version() {
+        let steps: [fn(&mut Value); 3] = [
+            |doc| doc["steps"] = json!("0"),
+            |doc| doc["steps"] = json!(format!("{}1", doc["steps"].as_str().unwrap_or_default())),
+            |doc| doc["steps"] = json!(format!("{}2", doc["steps"].as_str().unwrap_or_default())),
+        ];
+        let mut doc = json!({});
+        assert_eq!(run_migrations(&mut doc, &steps), Ok(0));
+        assert_eq!(doc, json!({ "version": 3, "steps": "012" }));
+
+        let mut doc = json!({ "version": 2, "steps": "x" });
+        assert_eq!(run_migrations(&mut doc, &steps), Ok(2));
+        assert_eq!(doc, json!({ "version": 3, "steps": "x2" }));
+    }
+
+    #[test]
+    fn newer_versions_are_rejected() {
+        let newer = format!(r#"{{ "version": {} }}"#, SETTINGS_VERSION + 1);
+        let err = parse(&newer).err().unwrap();
+        assert!(err.contains("newer GoyMusic"), "{}", err);
+        assert!(parse("[]").is_err());
+    }
+
+    #[test]
+    fn defaults_are_valid() {
+        assert_eq!(validate(&Settings::default()), Ok(()));
+    }
+
+    #[test]
+    fn each_invalid_value_is_reported() {
+        let cases: Vec<(&str, fn(&mut Settings))> = vec![
+            ("discord.client_id", |s| s.discord.client_id = "abc".to_string()),
+            ("lyrics.providers must list", |s| s.lyrics.providers.clear()),
+            ("lyrics.providers contains duplicates", |s| {
+                s.lyrics.providers = vec![LyricsProviderKind::Lrclib, LyricsProviderKind::Lrclib]
+            }),
+            ("lyrics.lrclib_url", |s| s.lyrics.lrclib_url = "ftp://x".to_string()),
+            ("bridge.request_timeout_secs", |s| s.bridge.request_timeout_secs = 0),
+            ("bridge.scrape_timeout_secs", |s| s.bridge.scrape_timeout_secs = 121),
+            ("bridge.page_load_wait_ms", |s| s.bridge.page_load_wait_ms = 30_001),
+            ("bridge.state_timeout_ms", |s| s.bridge.state_timeout_ms = 100),
+            ("remote.bind_address", |s| s.remote.bind_address = "localhost".to_string()),
+            ("remote.port", |s| s.remote.port = 0),
+            ("remote.token", |s| {
+                s.remote.enabled = true;
+                s.remote.token = "short".to_string();
+            }),
+            ("mpd.bind_address", |s| s.mpd.bind_address = "::x".to_string()),
+            ("mpd.port", |s| s.mpd.port = 0),
+            ("remote.port and mpd.port must differ", |s| {
+                s.remote.enabled = true;
+                s.remote.token = "0123456789abcdef".to_string();
+                s.mpd.enabled = true;
+                s.mpd.port = s.remote.port;
+            }),
+            ("scrobble.lastfm.api_url", |s| s.scrobble.lastfm.api_url = "example.com".to_string()),
+            ("scrobble.lastfm.auth_url", |s| s.scrobble.lastfm.auth_url = String::new()),
+            ("scrobble.listenbrainz.api_url", |s| s.scrobble.listenbrainz.api_url = "x".to_string()),
+            ("scrobble.listenbrainz.token", |s| s.scrobble.listenbrainz.enabled = true),
+        ];
+        for (expected, break_it) in cases {
+            let mut settings = Settings::default();
+            break_it(&mut settings);
+            let err = validate(&settings).err().unwrap_or_default();
+            assert!(err.contains(expected), "expected {:?}, got {:?}", expected, err);
+        }
+    }
+
+    #[test]
+    fn all_problems_are_reported_together() {
+        let mut settings = Settings::default();
+        settings.remote.port = 0;
+        settings.mpd.port = 0;
+        let err = validate(&settings).unwrap_err();
+        assert!(err.contains("remote.port") && err.contains("mpd.port"), "{}", err);
+    }
+
+    #[test]
+    fn merge_json_patches_nested_objects() {
+        let mut target = json!({
+            "discord": { "enabled": true, "incognito": false, "hidden_playlists": ["a"] },
+            "window": { "always_on_top": false },
+        });
+        merge_json(&mut target, json!({
+            "discord": { "incognito": true, "hidden_playlists": ["b", "c"] },
+            "remote": { "port": 1234 },
+        }));
+        assert_eq!(
+            target,
+            json!({
+                "discord": { "enabled": true, "incognito": true, "hidden_playlists": ["b", "c"] },
+                "window": { "always_on_top": false },
+                "remote": { "port": 1234 },
+            })
+        );
+
+        // A non-object patch replaces the value outright
+        merge_json(&mut target, json!({ "window": null }));
+        assert_eq!(target["window"], Value::Null);
+    }
+}
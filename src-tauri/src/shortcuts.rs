@@ -0,0 +1,106 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+use tauri::{Emitter, Manager};
+use tauri_plugin_global_shortcut::{GlobalShortcutExt, Shortcut, ShortcutEvent, ShortcutState};
+
+use crate::settings::{SettingsState, ShortcutSettings};
+
+/// Registered shortcut IDs and the action each one triggers
+#[derive(Default)]
+pub struct ShortcutsState(Mutex<HashMap<u32, &'static str>>);
+
+fn bindings(cfg: &ShortcutSettings) -> [(&'static str, &str); 8] {
+    [
+        ("play_pause", cfg.play_pause.as_str()),
+        ("next", cfg.next.as_str()),
+        ("previous", cfg.previous.as_str()),
+        ("volume_up", cfg.volume_up.as_str()),
+        ("volume_down", cfg.volume_down.as_str()),
+        ("like", cfg.like.as_str()),
+        ("dislike", cfg.dislike.as_str()),
+        ("show_window", cfg.show_window.as_str()),
+    ]
+}
+
+/// Run an action, emitting the same events as the media key handler where one exists
+fn trigger(app: &tauri::AppHandle, action: &str) {
+    match action {
+        "play_pause" => { let _ = app.emit("media-play-pause", ()); }
+        "next" => { let _ = app.emit("media-next", ()); }
+        "previous" => { let _ = app.emit("media-prev", ()); }
+        "volume_up" => { let _ = app.emit("media-volume-up", ()); }
+        "volume_down" => { let _ = app.emit("media-volume-down", ()); }
+        "like" => crate::rating::toggle_like(app),
+        "dislike" => crate::rating::toggle_dislike(app),
+        "show_window" => crate::tray::show_main_window(app),
+        _ => {}
+    }
+}
+
+fn on_shortcut(app: &tauri::AppHandle, shortcut: &Shortcut, event: ShortcutEvent) {
+    if event.state() != ShortcutState::Pressed {
+        return;
+    }
+    let action = app.state::<ShortcutsState>().0.lock().unwrap().get(&shortcut.id()).copied();
+    if let Some(action) = action {
+        trigger(app, action);
+    }
+}
+
+/// Replace all registered shortcuts with the ones in `cfg`.
+///
+/// Invalid or duplicated accelerators are rejected before anything is touched.
+/// Shortcuts already taken by another application are reported but don't stop the rest.
+pub fn apply(app: &tauri::AppHandle, cfg: &ShortcutSettings) -> Result<(), String> {
+    let mut parsed: Vec<(&'static str, &str, Shortcut)> = Vec::new();
+    if cfg.enabled {
+        for (action, accel) in bindings(cfg) {
+            if accel.trim().is_empty() {
+                continue;
+            }
+            let shortcut: Shortcut = accel
+                .parse()
+                .map_err(|e| format!("Invalid shortcut \"{}\" for {}: {}", accel, action, e))?;
+            if let Some((other, _, _)) = parsed.iter().find(|(_, _, s)| *s == shortcut) {
+                return Err(format!("\"{}\" is assigned to both {} and {}", accel, other, action));
+            }
+            parsed.push((action, accel, shortcut));
+        }
+    }
+
+    let manager = app.global_shortcut();
+    manager.unregister_all().map_err(|e| e.to_string())?;
+    let mut map = app.state::<ShortcutsState>().0.lock().unwrap();
+    map.clear();
+
+    let mut failed = Vec::new();
+    for (action, accel, shortcut) in parsed {
+        let id = shortcut.id();
+        match manager.register(shortcut) {
+            Ok(()) => { map.insert(id, action); }
+            Err(e) => failed.push(format!("{} ({}): {}", action, accel, e)),
+        }
+    }
+
+    if failed.is_empty() {
+        Ok(())
+    } else {
+        Err(format!("Could not register shortcuts: {}", failed.join(", ")))
+    }
+}
+
+/// Install the global shortcut plugin and register the saved bindings
+pub fn init(app: &tauri::App) -> tauri::Result<()> {
+    app.handle().plugin(
+        tauri_plugin_global_shortcut::Builder::new()
+            .with_handler(on_shortcut)
+            .build(),
+    )?;
+    app.manage(ShortcutsState::default());
+
+    let cfg = app.state::<SettingsState>().0.lock().unwrap().shortcuts.clone();
+    if let Err(e) = apply(app.handle(), &cfg) {
+        eprintln!("{}", e);
+    }
+    Ok(())
+}
@@ -0,0 +1,355 @@
+use serde::Serialize;
+use std::collections::HashMap;
+use tauri::State;
+
+use crate::csv;
+use crate::history::{self, HistoryEntry, HistoryState};
+
+const DAY_SECS: i64 = 24 * 3600;
+const WEEKDAYS: [&str; 7] = ["Mon", "Tue", "Wed", "Thu", "Fri", "Sat", "Sun"];
+
+/// Plays aggregated under one key: a track, artist, album, hour or weekday
+#[derive(Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Group {
+    pub name: String,
+    /// Set for tracks and albums
+    pub artist: String,
+    /// Set for tracks
+    pub video_id: String,
+    pub plays: u64,
+    /// Seconds actually played
+    pub listened: f64,
+    pub skips: u64,
+    /// `skips / plays`, 0 when there are no plays
+    pub skip_rate: f64,
+}
+
+impl Group {
+    fn named(name: &str) -> Self {
+        Self { name: name.to_string(), ..Default::default() }
+    }
+
+    fn add(&mut self, entry: &HistoryEntry) {
+        self.plays += 1;
+        self.listened += entry.listened;
+        self.skips += entry.skipped as u64;
+        self.skip_rate = self.skips as f64 / self.plays as f64;
+    }
+}
+
+/// Consecutive local days with at least one play
+#[derive(Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Streaks {
+    pub longest: u32,
+    /// Unix seconds of the local midnight the longest streak began, if any
+    pub longest_start: Option<i64>,
+    /// Streak running through today, or through yesterday if nothing's been played yet today
+    pub current: u32,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Report {
+    pub from: Option<i64>,
+    pub to: Option<i64>,
+    pub total: Group,
+    pub top_tracks: Vec<Group>,
+    pub top_artists: Vec<Group>,
+    pub top_albums: Vec<Group>,
+    /// 24 entries, local time
+    pub hours: Vec<Group>,
+    /// 7 entries, Monday first
+    pub weekdays: Vec<Group>,
+    /// Plays per `[weekday][hour]`
+    pub heatmap: Vec<Vec<u64>>,
+    pub streaks: Streaks,
+}
+
+/// Local day number since the epoch and the seconds into that day
+fn local_day(ts: i64, utc_offset_minutes: i32) -> (i64, i64) {
+    let local = ts + utc_offset_minutes as i64 * 60;
+    (local.div_euclid(DAY_SECS), local.rem_euclid(DAY_SECS))
+}
+
+/// 0 = Monday. Day 0 (1970-01-01) was a Thursday.
+fn weekday(day: i64) -> usize {
+    (day + 3).rem_euclid(7) as usize
+}
+
+/// Highest play count first, then most time listened, then name for a stable order
+fn top(groups: HashMap<String, Group>, limit: usize) -> Vec<Group> {
+    let mut groups: Vec<Group> = groups.into_values().collect();
+    groups.sort_by(|a, b| {
+        b.plays.cmp(&a.plays).then(b.listened.total_cmp(&a.listened)).then_with(|| a.name.cmp(&b.name))
+    });
+    groups.truncate(limit);
+    groups
+}
+
+fn streaks(days: &[i64], today: i64, utc_offset_minutes: i32) -> Streaks {
+    let mut result = Streaks::default();
+    let mut run = 0;
+    for (i, day) in days.iter().enumerate() {
+        run = if i > 0 && days[i - 1] + 1 == *day { run + 1 } else { 1 };
+        if run > result.longest {
+            result.longest = run;
+            result.longest_start = Some((day - run as i64 + 1) * DAY_SECS - utc_offset_minutes as i64 * 60);
+        }
+    }
+    if let Some(&last) = days.last() {
+        if last == today || last + 1 == today {
+            result.current = run;
+        }
+    }
+    result
+}
+
+/// `now` is Unix seconds, used to tell whether the latest streak is still running
+fn report(
+    entries: &[HistoryEntry],
+    from: Option<i64>,
+    to: Option<i64>,
+    utc_offset_minutes: i32,
+    limit: usize,
+    now: i64,
+) -> Report {
+    let mut total = Group::named("total");
+    let mut tracks: HashMap<String, Group> = HashMap::new();
+    let mut artists: HashMap<String, Group> = HashMap::new();
+    let mut albums: HashMap<String, Group> = HashMap::new();
+    let mut hours: Vec<Group> = (0..24).map(|h| Group::named(&format!("{:02}", h))).collect();
+    let mut weekdays: Vec<Group> = WEEKDAYS.iter().map(|d| Group::named(d)).collect();
+    let mut heatmap = vec![vec![0; 24]; 7];
+    let mut days: Vec<i64> = Vec::new();
+
+    for entry in entries {
+        total.add(entry);
+
+        // Entries are oldest first, so the latest metadata for a track wins
+        let track = tracks.entry(entry.video_id.clone()).or_default();
+        track.name = entry.title.clone();
+        track.artist = entry.artist.clone();
+        track.video_id = entry.video_id.clone();
+        track.add(entry);
+
+        if !entry.artist.is_empty() {
+            artists.entry(entry.artist.clone()).or_insert_with(|| Group::named(&entry.artist)).add(entry);
+        }
+        if !entry.album.is_empty() {
+            let key = format!("{}\u{0}{}", entry.album, entry.artist);
+            let album = albums.entry(key).or_insert_with(|| Group::named(&entry.album));
+            album.artist = entry.artist.clone();
+            album.add(entry);
+        }
+
+        let (day, secs) = local_day(entry.started_at, utc_offset_minutes);
+        let hour = (secs / 3600) as usize;
+        hours[hour].add(entry);
+        weekdays[weekday(day)].add(entry);
+        heatmap[weekday(day)][hour] += 1;
+        if days.last() != Some(&day) {
+            days.push(day);
+        }
+    }
+
+    let (today, _) = local_day(now, utc_offset_minutes);
+    Report {
+        from,
+        to,
+        total,
+        top_tracks: top(tracks, limit),
+        top_artists: top(artists, limit),
+        top_albums: top(albums, limit),
+        hours,
+        weekdays,
+        heatmap,
+        streaks: streaks(&days, today, utc_offset_minutes),
+    }
+}
+
+/// Long-form CSV: one row per group, with the streaks as `streak` rows whose `plays` column holds days
+fn to_csv(report: &Report) -> String {
+    let mut out = csv::row(&["section", "name", "artist", "video_id", "plays", "listened_secs", "skips", "skip_rate"]);
+    let sections: [(&str, &[Group]); 6] = [
+        ("total", std::slice::from_ref(&report.total)),
+        ("track", &report.top_tracks),
+        ("artist", &report.top_artists),
+        ("album", &report.top_albums),
+        ("hour", &report.hours),
+        ("weekday", &report.weekdays),
+    ];
+    for (section, groups) in sections {
+        for g in groups {
+            out.push_str(&csv::row(&[
+                section.to_string(),
+                g.name.clone(),
+                g.artist.clone(),
+                g.video_id.clone(),
+                g.plays.to_string(),
+                format!("{:.0}", g.listened),
+                g.skips.to_string(),
+                format!("{:.4}", g.skip_rate),
+            ]));
+        }
+    }
+    for (name, days) in [("longest", report.streaks.longest), ("current", report.streaks.current)] {
+        out.push_str(&csv::row(&["streak", name, "", "", days.to_string().as_str(), "", "", ""]));
+    }
+    out
+}
+
+fn build(
+    history: &HistoryState,
+    from: Option<i64>,
+    to: Option<i64>,
+    utc_offset_minutes: Option<i32>,
+    limit: Option<usize>,
+) -> Result<Report, String> {
+    let entries = history::entries(&history.0.lock().unwrap(), from, to)?;
+    let now = crate::plays::unix_now();
+    Ok(report(&entries, from, to, utc_offset_minutes.unwrap_or(0), limit.unwrap_or(10), now))
+}
+
+/// Aggregate plays started in `[from, to)`. `utc_offset_minutes` places plays in the user's local
+/// hours and days (east of UTC is positive).
+#[tauri::command]
+pub fn stats_report(
+    history: State<'_, HistoryState>,
+    from: Option<i64>,
+    to: Option<i64>,
+    utc_offset_minutes: Option<i32>,
+    limit: Option<usize>,
+) -> Result<Report, String> {
+    build(&history, from, to, utc_offset_minutes, limit)
+}
+
+/// Write the report to `path` as `json` or `csv`
+#[tauri::command]
+pub fn stats_export(
+    history: State<'_, HistoryState>,
+    from: Option<i64>,
+    to: Option<i64>,
+    utc_offset_minutes: Option<i32>,
+    limit: Option<usize>,
+    format: String,
+    path: String,
+) -> Result<(), String> {
+    let report = build(&history, from, to, utc_offset_minutes, limit)?;
+    let text = match format.as_str() {
+        "json" => serde_json::to_string_pretty(&report).map_err(|e| e.to_string())?,
+        "csv" => to_csv(&report),
+        other => return Err(format!("Unknown export format: {}", other)),
+    };
+    std::fs::write(&path, text).map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HOUR: i64 = 3600;
+
+    fn play(video_id: &str, artist: &str, album: &str, started_at: i64, listened: f64, skipped: bool) -> HistoryEntry {
+        HistoryEntry {
+            id: 0,
+            video_id: video_id.to_string(),
+            title: format!("Title {}", video_id),
+            artist: artist.to_string(),
+            album: album.to_string(),
+            duration: 240.0,
+            started_at,
+            listened,
+            completed: !skipped,
+            skipped,
+        }
+    }
+
+    #[test]
+    fn local_day_shifts_by_the_offset() {
+        assert_eq!(local_day(0, 0), (0, 0));
+        assert_eq!(local_day(-1, 0), (-1, DAY_SECS - 1));
+        assert_eq!(local_day(0, -60), (-1, 23 * HOUR));
+        assert_eq!(local_day(23 * HOUR, 120), (1, HOUR));
+        assert_eq!(local_day(DAY_SECS + 2 * HOUR, -300), (0, 21 * HOUR));
+    }
+
+    #[test]
+    fn weekday_counts_from_monday() {
+        // 1970-01-01 was a Thursday, 1970-01-05 a Monday and 1969-12-28 a Sunday
+        assert_eq!(WEEKDAYS[weekday(0)], "Thu");
+        assert_eq!(WEEKDAYS[weekday(4)], "Mon");
+        assert_eq!(WEEKDAYS[weekday(-1)], "Wed");
+        assert_eq!(WEEKDAYS[weekday(-4)], "Sun");
+    }
+
+    #[test]
+    fn streaks_find_the_longest_run_across_gaps() {
+        let s = streaks(&[1, 2, 3, 5, 6, 10], 10, 0);
+        assert_eq!((s.longest, s.longest_start, s.current), (3, Some(DAY_SECS), 1));
+
+        // The first of equally long runs wins
+        let s = streaks(&[1, 2, 5, 6], 20, 0);
+        assert_eq!((s.longest, s.longest_start, s.current), (2, Some(DAY_SECS), 0));
+
+        // Local midnight west of UTC is later in UTC
+        let s = streaks(&[2], 2, -300);
+        assert_eq!(s.longest_start, Some(2 * DAY_SECS + 5 * HOUR));
+
+        let s = streaks(&[], 5, 0);
+        assert_eq!((s.longest, s.longest_start, s.current), (0, None, 0));
+    }
+
+    #[test]
+    fn current_streak_survives_until_the_day_after() {
+        assert_eq!(streaks(&[3, 4, 5], 5, 0).current, 3);
+        assert_eq!(streaks(&[3, 4, 5], 6, 0).current, 3);
+        assert_eq!(streaks(&[3, 4, 5], 7, 0).current, 0);
+    }
+
+    #[test]
+    fn report_groups_plays() {
+        let entries = [
+            play("a", "Artist A", "Album X", DAY_SECS + 10 * HOUR, 100.0, false),
+            play("a", "Artist A", "Album X", 2 * DAY_SECS + 10 * HOUR, 50.0, true),
+            play("b", "Artist B", "", 2 * DAY_SECS + 11 * HOUR, 200.0, false),
+            play("c", "Artist A", "Album X", 4 * DAY_SECS + 12 * HOUR, 30.0, true),
+        ];
+        let r = report(&entries, None, None, 0, 10, 5 * DAY_SECS + 12 * HOUR);
+
+        assert_eq!((r.total.plays, r.total.listened, r.total.skips, r.total.skip_rate), (4, 380.0, 2, 0.5));
+        let tracks: Vec<_> = r.top_tracks.iter().map(|g| (g.name.as_str(), g.video_id.as_str(), g.plays)).collect();
+        assert_eq!(tracks, [("Title a", "a", 2), ("Title b", "b", 1), ("Title c", "c", 1)]);
+        let artists: Vec<_> = r.top_artists.iter().map(|g| (g.name.as_str(), g.plays)).collect();
+        assert_eq!(artists, [("Artist A", 3), ("Artist B", 1)]);
+        let albums: Vec<_> = r.top_albums.iter().map(|g| (g.name.as_str(), g.artist.as_str(), g.plays)).collect();
+        assert_eq!(albums, [("Album X", "Artist A", 3)]);
+
+        assert_eq!((r.hours[10].plays, r.hours[11].plays, r.hours[12].plays), (2, 1, 1));
+        assert_eq!(r.hours.iter().map(|g| g.plays).sum::<u64>(), 4);
+        assert_eq!(r.weekdays[weekday(2)].plays, 2);
+        assert_eq!(r.heatmap[weekday(2)][11], 1);
+        assert_eq!((r.streaks.longest, r.streaks.longest_start, r.streaks.current), (2, Some(DAY_SECS), 1));
+
+        assert_eq!(report(&entries, None, None, 0, 1, 0).top_tracks.len(), 1);
+    }
+
+    #[test]
+    fn report_uses_local_days_west_of_utc() {
+        // 02:00 UTC is 21:00 the previous evening at UTC-5
+        let entries = [
+            play("a", "Artist", "", 3 * DAY_SECS + 2 * HOUR, 60.0, false),
+            play("b", "Artist", "", 4 * DAY_SECS + 2 * HOUR, 60.0, false),
+        ];
+        let r = report(&entries, None, None, -300, 10, 4 * DAY_SECS + 23 * HOUR);
+
+        assert_eq!(r.hours[21].plays, 2);
+        assert_eq!((r.weekdays[5].name.as_str(), r.weekdays[5].plays), ("Sat", 1));
+        assert_eq!((r.weekdays[6].name.as_str(), r.weekdays[6].plays), ("Sun", 1));
+        assert_eq!(r.heatmap[5][21], 1);
+        assert_eq!(r.streaks.longest_start, Some(2 * DAY_SECS + 5 * HOUR));
+        // Nothing played yet on local day 4, so the streak that ended yesterday still counts
+        assert_eq!((r.streaks.longest, r.streaks.current), (2, 2));
+    }
+}
@@ -0,0 +1,136 @@
+use tauri::menu::{CheckMenuItem, Menu, MenuItem, PredefinedMenuItem};
+use tauri::tray::{MouseButton, MouseButtonState, TrayIconBuilder, TrayIconEvent};
+use tauri::{Emitter, Manager, Wry};
+
+use crate::now_playing::{LikeStatus, NowPlaying, RepeatMode};
+use crate::rating;
+
+const TRAY_ID: &str = "main";
+
+/// Menu items whose text or state follows playback
+pub struct TrayState {
+    now_playing: MenuItem<Wry>,
+    play_pause: MenuItem<Wry>,
+    like: MenuItem<Wry>,
+    dislike: MenuItem<Wry>,
+    library: MenuItem<Wry>,
+    shuffle: CheckMenuItem<Wry>,
+    repeat: MenuItem<Wry>,
+}
+
+fn truncate(s: &str, max: usize) -> String {
+    if s.chars().count() <= max {
+        s.to_string()
+    } else {
+        s.chars().take(max - 1).collect::<String>() + "…"
+    }
+}
+
+pub fn show_main_window(app: &tauri::AppHandle) {
+    if let Some(window) = app.get_webview_window("main") {
+        let _ = window.show();
+        let _ = window.set_focus();
+    }
+}
+
+fn on_menu_event(app: &tauri::AppHandle, id: &str) {
+    match id {
+        "play_pause" => { let _ = app.emit("media-play-pause", ()); }
+        "next" => { let _ = app.emit("media-next", ()); }
+        "prev" => { let _ = app.emit("media-prev", ()); }
+        "shuffle" => { let _ = app.emit("media-shuffle", ()); }
+        "repeat" => { let _ = app.emit("media-repeat", ()); }
+        "like" => rating::toggle_like(app),
+        "dislike" => rating::toggle_dislike(app),
+        "library" => rating::toggle_library(app),
+        "show" => show_main_window(app),
+        "quit" => crate::quit(app),
+        _ => {}
+    }
+}
+
+/// Create the tray icon and its playback menu
+pub fn build(app: &tauri::App) -> tauri::Result<()> {
+    let now_playing = MenuItem::with_id(app, "now_playing", "Not playing", false, None::<&str>)?;
+    let play_pause = MenuItem::with_id(app, "play_pause", "Play", true, None::<&str>)?;
+    let next = MenuItem::with_id(app, "next", "Next", true, None::<&str>)?;
+    let prev = MenuItem::with_id(app, "prev", "Previous", true, None::<&str>)?;
+    let like = MenuItem::with_id(app, "like", "Like", false, None::<&str>)?;
+    let dislike = MenuItem::with_id(app, "dislike", "Dislike", false, None::<&str>)?;
+    let library = MenuItem::with_id(app, "library", "Add to library", false, None::<&str>)?;
+    let shuffle = CheckMenuItem::with_id(app, "shuffle", "Shuffle", true, false, None::<&str>)?;
+    let repeat = MenuItem::with_id(app, "repeat", "Repeat: Off", true, None::<&str>)?;
+    let show = MenuItem::with_id(app, "show", "Show GoyMusic", true, None::<&str>)?;
+    let quit = MenuItem::with_id(app, "quit", "Quit", true, None::<&str>)?;
+
+    let menu = Menu::with_items(app, &[
+        &now_playing,
+        &PredefinedMenuItem::separator(app)?,
+        &play_pause,
+        &next,
+        &prev,
+        &like,
+        &dislike,
+        &library,
+        &PredefinedMenuItem::separator(app)?,
+        &shuffle,
+        &repeat,
+        &PredefinedMenuItem::separator(app)?,
+        &show,
+        &quit,
+    ])?;
+
+    TrayIconBuilder::with_id(TRAY_ID)
+        .icon(app.default_window_icon().unwrap().clone())
+        .tooltip("GoyMusic")
+        .menu(&menu)
+        .show_menu_on_left_click(false)
+        .on_menu_event(|app, event| on_menu_event(app, event.id().as_ref()))
+        .on_tray_icon_event(|tray, event| {
+            if let TrayIconEvent::Click {
+                button: MouseButton::Left,
+                button_state: MouseButtonState::Up,
+                ..
+            } = event {
+                show_main_window(tray.app_handle());
+            }
+        })
+        .build(app)?;
+
+    app.manage(TrayState { now_playing, play_pause, like, dislike, library, shuffle, repeat });
+    Ok(())
+}
+
+/// Reflect the current track and playback state in the tray menu and tooltip
+pub fn refresh(app: &tauri::AppHandle, np: &NowPlaying) {
+    let Some(items) = app.try_state::<TrayState>() else { return };
+
+    let label = match &np.track {
+        Some(t) => truncate(&format!("{} — {}", t.title, t.artist), 60),
+        None => "Not playing".to_string(),
+    };
+    let _ = items.now_playing.set_text(&label);
+    let _ = items.play_pause.set_text(if np.is_playing { "Pause" } else { "Play" });
+    let _ = items.like.set_enabled(np.track.is_some());
+    let _ = items.like.set_text(if np.like_status == LikeStatus::Like { "Remove like" } else { "Like" });
+    let _ = items.dislike.set_enabled(np.track.is_some());
+    let _ = items.dislike.set_text(if np.like_status == LikeStatus::Dislike { "Remove dislike" } else { "Dislike" });
+    // Unknown until the lookup for the track finishes
+    let _ = items.library.set_enabled(np.track.is_some() && np.in_library.is_some());
+    let _ = items.library.set_text(if np.in_library == Some(true) { "Remove from library" } else { "Add to library" });
+    let _ = items.shuffle.set_checked(np.shuffle);
+    let _ = items.repeat.set_text(match np.repeat {
+        RepeatMode::Off => "Repeat: Off",
+        RepeatMode::All => "Repeat: All",
+        RepeatMode::One => "Repeat: One",
+    });
+
+    // Windows caps tray tooltips at 127 characters
+    let tooltip = match &np.track {
+        Some(t) => truncate(&format!("{}\n{}", t.title, t.artist), 120),
+        None => "GoyMusic".to_string(),
+    };
+    if let Some(tray) = app.tray_by_id(TRAY_ID) {
+        let _ = tray.set_tooltip(Some(tooltip));
+    }
+}
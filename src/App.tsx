@@ -9,6 +9,7 @@ import { TitleBar } from './components/organisms/TitleBar';
 import { SettingsView } from './components/organisms/SettingsView';
 import { LyricsView } from './components/organisms/LyricsView';
 import { isLoggedIn, clearTokens, getLibraryPlaylists, YTMPlaylist } from './api/yt';
+import { player, OpenLink } from './api/player';
 import './styles/theme.css';
 
 export type ViewType = 'liked' | 'playlist' | 'search' | 'settings';
@@ -75,6 +76,14 @@ function App() {
     init();
   }, []);
 
+  // Play links the app was launched with, once the webview can resolve them
+  useEffect(() => {
+    if (!isAuthenticated) return;
+    invoke<OpenLink[]>('take_pending_links')
+      .then(links => links.forEach(link => player.openLink(link)))
+      .catch(e => console.error('Failed to open links', e));
+  }, [isAuthenticated]);
+
   const loadPlaylists = async () => {
     try {
       const pls = await getLibraryPlaylists();
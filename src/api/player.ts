@@ -1,13 +1,20 @@
 import { invoke } from '@tauri-apps/api/core';
 import { listen } from '@tauri-apps/api/event';
-import { YTMTrack } from './yt';
+import { YTMTrack, getPlaylistTracks } from './yt';
 
 type PlayerCallback = () => void;
 
+/** A `goymusic://` or `music.youtube.com` link resolved by Rust */
+export type OpenLink =
+    | { kind: 'track'; track: { videoId: string; title: string; artist: string; album: string; thumbUrl: string; duration: number } }
+    | { kind: 'playlist'; playlistId: string; videoId: string | null };
+
 class PlayerStore {
     currentTrack: YTMTrack | null = null;
     queue: YTMTrack[] = [];
     queueIndex: number = -1;
+    /** Playlist the current queue was started from, if any */
+    playlistId: string | null = null;
     isPlaying: boolean = false;
     currentTime: number = 0;
     duration: number = 0;
@@ -26,9 +33,51 @@ class PlayerStore {
 
     private notify() {
         this.listeners.forEach(cb => cb());
+        this.updateNowPlaying();
+        this.updateQueue();
         this.updateRpc();
     }
 
+    /** Mirror player state into the Rust backend (tray, notifications, remote control) */
+    private updateNowPlaying() {
+        const t = this.currentTrack;
+        invoke('ytm_update_now_playing', {
+            track: t ? {
+                videoId: t.id,
+                title: t.title,
+                artist: t.artist,
+                album: t.album,
+                thumbUrl: t.thumbUrl,
+                duration: this.duration
+            } : null,
+            isPlaying: this.isPlaying,
+            currentTime: this.currentTime,
+            volume: this.volume,
+            shuffle: this.shuffle,
+            repeat: this.repeat
+        }).catch(() => { });
+    }
+
+    private queueSignature = '';
+
+    /** Mirror the queue for remote clients, only when it actually changed */
+    private updateQueue() {
+        const signature = this.queueIndex + '|' + this.queue.map(t => t.id).join(',');
+        if (signature === this.queueSignature) return;
+        this.queueSignature = signature;
+        invoke('ytm_update_queue', {
+            tracks: this.queue.map(t => ({
+                videoId: t.id,
+                title: t.title,
+                artist: t.artist,
+                album: t.album,
+                thumbUrl: t.thumbUrl,
+                duration: this.parseDuration(t.duration)
+            })),
+            index: this.queueIndex >= 0 ? this.queueIndex : null
+        }).catch(() => { });
+    }
+
     private rpcLastVideoId = '';
     private rpcLastIsPlaying = false;
     private rpcLastTime = 0;
@@ -57,6 +106,7 @@ class PlayerStore {
 
         const title = this.currentTrack?.title || '';
         const artist = this.currentTrack?.artist || '';
+        const album = this.currentTrack?.album || '';
         const thumbUrl = this.currentTrack?.thumbUrl || '';
 
         try {
@@ -64,8 +114,10 @@ class PlayerStore {
             await invoke('ytm_update_discord_rpc', {
                 title,
                 artist,
+                album,
                 videoId,
                 thumbUrl,
+                playlistId: this.playlistId,
                 isPlaying: this.isPlaying,
                 currentTime: this.currentTime,
                 duration: this.duration
@@ -83,10 +135,11 @@ class PlayerStore {
     }
 
     /** Load a list of tracks and start playing from index */
-    async playTrackList(tracks: YTMTrack[], startIndex: number = 0) {
+    async playTrackList(tracks: YTMTrack[], startIndex: number = 0, playlistId: string | null = null) {
         // If the tracks are literally the same reference as the current queue, just seek index
         if (tracks !== this.queue) {
             this.queue = [...tracks];
+            this.playlistId = playlistId;
         }
         this.queueIndex = startIndex;
         await this.playCurrentTrack();
@@ -112,10 +165,55 @@ class PlayerStore {
     async playSingle(track: YTMTrack) {
         this.currentTrack = track;
         this.queue = [track];
+        this.playlistId = null;
         this.queueIndex = 0;
         await this.startPlayback(track);
     }
 
+    private linkTrack(link: Extract<OpenLink, { kind: 'track' }>): YTMTrack {
+        const t = link.track;
+        const secs = Math.round(t.duration);
+        return {
+            id: t.videoId,
+            title: t.title,
+            artist: t.artist,
+            album: t.album,
+            duration: secs > 0 ? `${Math.floor(secs / 60)}:${String(secs % 60).padStart(2, '0')}` : '',
+            thumbUrl: t.thumbUrl,
+        };
+    }
+
+    /** Play a link opened from outside the app. Single tracks are slotted in after the current one. */
+    async openLink(link: OpenLink) {
+        if (link.kind === 'track') {
+            const track = this.linkTrack(link);
+            if (this.queue.length === 0) {
+                await this.playSingle(track);
+            } else {
+                // Step onto the inserted track directly; next() would pick a random one when shuffling
+                this.playNext(track);
+                this.queueIndex++;
+                await this.playCurrentTrack();
+            }
+        } else {
+            const tracks = await getPlaylistTracks(link.playlistId);
+            if (tracks.length === 0) return;
+            const start = Math.max(0, tracks.findIndex(t => t.id === link.videoId));
+            await this.playTrackList(tracks, start, link.playlistId);
+        }
+    }
+
+    /** Append a link's track(s) to the end of the queue without interrupting playback */
+    async enqueueLink(link: OpenLink) {
+        const tracks = link.kind === 'track' ? [this.linkTrack(link)] : await getPlaylistTracks(link.playlistId);
+        if (this.queue.length === 0) {
+            await this.playTrackList(tracks, 0, link.kind === 'playlist' ? link.playlistId : null);
+        } else {
+            this.queue.push(...tracks);
+            this.notify();
+        }
+    }
+
     private async playCurrentTrack() {
         if (this.queueIndex < 0 || this.queueIndex >= this.queue.length) return;
         this.currentTrack = this.queue[this.queueIndex];
@@ -260,3 +358,12 @@ export const player = new PlayerStore();
 listen('media-play-pause', () => player.togglePlay());
 listen('media-next', () => player.next());
 listen('media-prev', () => player.prev());
+listen('media-shuffle', () => player.toggleShuffle());
+listen('media-repeat', () => player.toggleRepeat());
+listen<OpenLink>('open-link', (e) => player.openLink(e.payload));
+listen<OpenLink>('enqueue-link', (e) => player.enqueueLink(e.payload));
+listen<number>('media-play-index', (e) => player.playTrackList(player.queue, e.payload, player.playlistId));
+listen<number>('media-seek', (e) => player.seek(e.payload));
+listen<number>('media-volume', (e) => player.setVolume(e.payload));
+listen('media-volume-up', () => player.setVolume(Math.min(100, player.volume + 5)));
+listen('media-volume-down', () => player.setVolume(Math.max(0, player.volume - 5)));
@@ -1,12 +1,19 @@
 import React, { useState, useEffect, useMemo, useRef } from 'react';
 import { invoke } from '@tauri-apps/api/core';
+import { listen } from '@tauri-apps/api/event';
 import { player } from '../../api/player';
 import styles from './LyricsView.module.css';
 import { Loader2, Music4 } from 'lucide-react';
 
+interface LyricLine {
+    time: number;
+    text: string;
+    romanized?: string;
+}
+
 interface LyricsData {
-    syncedLyrics: string;
-    plainLyrics: string;
+    lines: LyricLine[];
+    plain: string;
     instrumental: boolean;
 }
 
@@ -15,7 +22,7 @@ export const LyricsView: React.FC = () => {
     const [loading, setLoading] = useState(false);
     const [error, setError] = useState('');
     const [trackId, setTrackId] = useState('');
-    const [currentTime, setCurrentTime] = useState(0);
+    const [activeLineIndex, setActiveLineIndex] = useState(-1);
     const [userScrolled, setUserScrolled] = useState(false);
     const scrollTimeout = useRef<number | null>(null);
     const scrollRef = useRef<HTMLDivElement>(null);
@@ -27,17 +34,24 @@ export const LyricsView: React.FC = () => {
             if (current) {
                 if (current.id !== trackId) {
                     setTrackId(current.id);
-                    fetchLyrics(current.title, current.artist);
+                    fetchLyrics(current.id, current.title, current.artist, current.album, player.duration);
                 }
-                setCurrentTime(player.currentTime);
             }
         });
     }, [trackId]);
 
+    // The backend tracks playback position and tells us which line is active
+    useEffect(() => {
+        const unlisten = listen<{ index: number | null }>('lyrics-line', (e) => {
+            setActiveLineIndex(e.payload.index ?? -1);
+        });
+        return () => { unlisten.then(fn => fn()); };
+    }, []);
+
     // Keep a ref to track the latest fetch to avoid race conditions
     const fetchIdRef = useRef<number>(0);
 
-    const fetchLyrics = async (title: string, artist: string) => {
+    const fetchLyrics = async (videoId: string, title: string, artist: string, album: string, duration: number) => {
         const currentFetchId = ++fetchIdRef.current;
 
         setLoading(true);
@@ -46,21 +60,20 @@ export const LyricsView: React.FC = () => {
 
         try {
             // Use Rust backend to bypass CORS
-            const data: any = await invoke('fetch_lyrics', {
+            const data = await invoke<LyricsData | null>('fetch_lyrics', {
                 trackName: title,
-                artistName: artist
+                artistName: artist,
+                albumName: album || null,
+                duration: duration > 0 ? duration : null,
+                videoId
             });
 
             // If another fetch was started after this one, ignore these results
             if (fetchIdRef.current !== currentFetchId) return;
 
-            if (data && Array.isArray(data) && data.length > 0) {
+            if (data) {
                 setError('');
-                setLyrics({
-                    syncedLyrics: data[0].syncedLyrics || '',
-                    plainLyrics: data[0].plainLyrics || '',
-                    instrumental: data[0].instrumental || false
-                });
+                setLyrics(data);
             } else {
                 setLyrics(null);
                 setError('No lyrics found for this track.');
@@ -77,29 +90,8 @@ export const LyricsView: React.FC = () => {
     };
 
     const parsedLyrics = useMemo(() => {
-        if (!lyrics?.syncedLyrics) return [];
-        return lyrics.syncedLyrics.split('\n').map((line, idx) => {
-            const match = line.match(/^\[(\d{2}):(\d{2}\.\d{2})\](.*)/);
-            if (match) {
-                const time = parseInt(match[1]) * 60 + parseFloat(match[2]);
-                return { time, text: match[3], id: idx };
-            }
-            return { time: -1, text: line, id: idx };
-        }).filter(l => l.time >= 0);
-    }, [lyrics?.syncedLyrics]);
-
-    const activeLineIndex = useMemo(() => {
-        if (parsedLyrics.length === 0) return -1;
-        // Find the last lyric line that has time <= currentTime + offset
-        // An offset of +0.6s helps people read slightly ahead and masks any polling delay.
-        const adjustedTime = currentTime + 0.6;
-        for (let i = parsedLyrics.length - 1; i >= 0; i--) {
-            if (adjustedTime >= parsedLyrics[i].time) {
-                return i;
-            }
-        }
-        return 0;
-    }, [parsedLyrics, currentTime]);
+        return (lyrics?.lines || []).map((line, idx) => ({ ...line, id: idx }));
+    }, [lyrics?.lines]);
 
     useEffect(() => {
         if (activeLineIndex >= 0 && scrollRef.current && lyricsContainerRef.current && !userScrolled) {
@@ -168,11 +160,12 @@ export const LyricsView: React.FC = () => {
                                         style={{ cursor: 'pointer' }}
                                     >
                                         {line.text || '♪'}
+                                        {line.romanized && <span className={styles.romanized}>{line.romanized}</span>}
                                     </p>
                                 );
                             })
                         ) : (
-                            lyrics.plainLyrics?.split('\n').map((line, i) => (
+                            lyrics.plain?.split('\n').map((line, i) => (
                                 <p key={i} className={styles.lyricLine}>{line || '♪'}</p>
                             ))
                         )}
@@ -314,7 +314,7 @@ export const MainView: React.FC<MainViewProps> = ({ activeView, isAuthenticated,
                 thumbUrl={track.thumbUrl}
                 isActive={activeTrackId === track.id}
                 isPlaying={isPlaying}
-                onClick={() => player.playTrackList(tracks, i)}
+                onClick={() => player.playTrackList(tracks, i, activeView.type === 'liked' ? 'LM' : activeView.playlistId || null)}
               />
             ))
           )}